@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE locations ADD COLUMN time_zone TEXT NOT NULL DEFAULT 'Australia/Perth';
@@ -0,0 +1,6 @@
+-- Add migration script here
+ALTER TABLE locations ADD COLUMN latitude DOUBLE PRECISION;
+ALTER TABLE locations ADD COLUMN longitude DOUBLE PRECISION;
+
+UPDATE locations SET latitude = -32.3917, longitude = 115.8669 WHERE bom_radar_id = 'IDR703';
+UPDATE locations SET latitude = -31.9275, longitude = 115.9764 WHERE bom_radar_id = 'IDR263';
@@ -0,0 +1,18 @@
+-- Add migration script here
+CREATE TABLE rain_alerts (
+	id SERIAL PRIMARY KEY,
+	user_id BIGINT NOT NULL,
+	bom_radar_id TEXT NOT NULL,
+	latitude DOUBLE PRECISION NOT NULL,
+	longitude DOUBLE PRECISION NOT NULL,
+	radius_km DOUBLE PRECISION NOT NULL DEFAULT 10,
+	threshold DOUBLE PRECISION NOT NULL,
+	cooldown_minutes INTEGER NOT NULL DEFAULT 120,
+	quiet_start_hour INTEGER,
+	quiet_end_hour INTEGER,
+	time_zone TEXT NOT NULL DEFAULT 'Australia/Perth',
+	last_alerted_at TIMESTAMP WITHOUT TIME ZONE,
+	created_at TIMESTAMP WITHOUT TIME ZONE DEFAULT now()
+);
+
+CREATE INDEX rain_alerts_user_id_idx ON rain_alerts (user_id);
@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE forecast_schedules (
+	id SERIAL PRIMARY KEY,
+	guild_id BIGINT NOT NULL,
+	channel_id BIGINT NOT NULL,
+	location TEXT NOT NULL,
+	days INTEGER NOT NULL DEFAULT 7,
+	post_time TIME NOT NULL,
+	time_zone TEXT NOT NULL DEFAULT 'Australia/Perth',
+	last_posted_on DATE,
+	created_by BIGINT NOT NULL,
+	created_at TIMESTAMP WITHOUT TIME ZONE DEFAULT now()
+);
+
+CREATE INDEX forecast_schedules_guild_id_idx ON forecast_schedules (guild_id);
@@ -0,0 +1,22 @@
+-- Add migration script here
+CREATE TABLE warnings (
+	id TEXT PRIMARY KEY,
+	state TEXT NOT NULL,
+	kind TEXT NOT NULL,
+	title TEXT NOT NULL,
+	link TEXT NOT NULL,
+	issued_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+	active BOOLEAN NOT NULL DEFAULT TRUE,
+	created_at TIMESTAMP WITHOUT TIME ZONE DEFAULT now()
+);
+
+CREATE INDEX warnings_state_idx ON warnings (state);
+
+CREATE TABLE warning_subscriptions (
+	id SERIAL PRIMARY KEY,
+	guild_id BIGINT NOT NULL,
+	channel_id BIGINT NOT NULL,
+	state TEXT NOT NULL,
+	created_at TIMESTAMP WITHOUT TIME ZONE DEFAULT now(),
+	UNIQUE (channel_id, state)
+);
@@ -0,0 +1,6 @@
+-- Add migration script here
+ALTER TABLE locations ADD COLUMN observation_product TEXT;
+ALTER TABLE locations ADD COLUMN observation_station INTEGER;
+
+UPDATE locations SET observation_product = 'IDW60901', observation_station = 94608 WHERE bom_radar_id = 'IDR703';
+UPDATE locations SET observation_product = 'IDW60901', observation_station = 94610 WHERE bom_radar_id = 'IDR263';
@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE observation_history (
+	station INTEGER NOT NULL,
+	observed_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+	air_temp DOUBLE PRECISION,
+	apparent_t DOUBLE PRECISION,
+	rel_hum INTEGER,
+	press DOUBLE PRECISION,
+	wind_dir TEXT,
+	wind_spd_kmh INTEGER,
+	gust_kmh INTEGER,
+	rain_since_9am DOUBLE PRECISION,
+	created_at TIMESTAMP WITHOUT TIME ZONE DEFAULT now(),
+	PRIMARY KEY (station, observed_at)
+);
@@ -0,0 +1,19 @@
+-- Add migration script here
+CREATE TABLE forecast_locations (
+	id TEXT PRIMARY KEY,
+	name TEXT NOT NULL,
+	provider TEXT NOT NULL DEFAULT 'willyweather',
+	willyweather_id TEXT,
+	latitude DOUBLE PRECISION,
+	longitude DOUBLE PRECISION,
+	precis_product TEXT,
+	precis_area TEXT,
+	created_at TIMESTAMP WITHOUT TIME ZONE DEFAULT now()
+);
+
+INSERT INTO forecast_locations (id, name, provider, willyweather_id, latitude, longitude, precis_product, precis_area)
+	VALUES ('14576', 'Perth', 'willyweather', '14576', -31.9523, 115.8613, 'IDW14199', 'Perth');
+
+-- australind isn't in the precis product, bunbury is next door
+INSERT INTO forecast_locations (id, name, provider, willyweather_id, latitude, longitude, precis_product, precis_area)
+	VALUES ('15864', 'Australind', 'willyweather', '15864', -33.2792, 115.7150, 'IDW14199', 'Bunbury');
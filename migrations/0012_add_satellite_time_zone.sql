@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE satellites ADD COLUMN time_zone TEXT NOT NULL DEFAULT 'Australia/Perth';
@@ -0,0 +1,74 @@
+-- Add migration script here
+ALTER TABLE locations ADD COLUMN range_km INTEGER NOT NULL DEFAULT 128;
+ALTER TABLE locations ADD COLUMN state TEXT;
+ALTER TABLE locations ADD COLUMN active BOOLEAN NOT NULL DEFAULT TRUE;
+-- radars with a priority above 0 are refreshed in the background, highest
+-- first, the rest are only fetched when someone asks for them
+ALTER TABLE locations ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+CREATE UNIQUE INDEX locations_bom_radar_id_idx ON locations (bom_radar_id);
+
+UPDATE locations SET state = 'WA', priority = 10 WHERE bom_radar_id = 'IDR703';
+UPDATE locations SET state = 'WA', priority = 5 WHERE bom_radar_id = 'IDR263';
+
+-- the 128km product of every other site in the network
+INSERT INTO locations (bom_radar_id, name, state, latitude, longitude, time_zone) VALUES
+	('IDR023', 'Melbourne', 'VIC', -37.8553, 144.7554, 'Australia/Melbourne'),
+	('IDR493', 'Yarrawonga', 'VIC', -36.0297, 146.0228, 'Australia/Melbourne'),
+	('IDR683', 'Bairnsdale', 'VIC', -37.8876, 147.5755, 'Australia/Melbourne'),
+	('IDR953', 'Rainbow', 'VIC', -35.9975, 142.0133, 'Australia/Melbourne'),
+	('IDR973', 'Mildura', 'VIC', -34.2870, 141.5980, 'Australia/Melbourne'),
+	('IDR143', 'Mt Gambier', 'SA', -37.7477, 140.7746, 'Australia/Adelaide'),
+	('IDR643', 'Adelaide (Buckland Park)', 'SA', -34.6170, 138.4690, 'Australia/Adelaide'),
+	('IDR463', 'Adelaide (Sellicks Hill)', 'SA', -35.3300, 138.5000, 'Australia/Adelaide'),
+	('IDR273', 'Woomera', 'SA', -31.1558, 136.8054, 'Australia/Adelaide'),
+	('IDR333', 'Ceduna', 'SA', -32.1298, 133.6963, 'Australia/Adelaide'),
+	('IDR713', 'Sydney (Terrey Hills)', 'NSW', -33.7008, 151.2094, 'Australia/Sydney'),
+	('IDR033', 'Wollongong (Appin)', 'NSW', -34.2625, 150.8752, 'Australia/Sydney'),
+	('IDR043', 'Newcastle', 'NSW', -32.7300, 152.0270, 'Australia/Sydney'),
+	('IDR283', 'Grafton', 'NSW', -29.6220, 152.9510, 'Australia/Sydney'),
+	('IDR693', 'Namoi (Blackjack Mountain)', 'NSW', -31.0236, 150.1917, 'Australia/Sydney'),
+	('IDR533', 'Moree', 'NSW', -29.5000, 149.8500, 'Australia/Sydney'),
+	('IDR553', 'Wagga Wagga', 'NSW', -35.1669, 147.4665, 'Australia/Sydney'),
+	('IDR943', 'Hillston', 'NSW', -33.5520, 145.5290, 'Australia/Sydney'),
+	('IDR933', 'Brewarrina', 'NSW', -29.9600, 146.8130, 'Australia/Sydney'),
+	('IDR963', 'Yeoval', 'NSW', -32.7442, 148.7081, 'Australia/Sydney'),
+	('IDR403', 'Canberra (Captains Flat)', 'NSW', -35.6614, 149.5122, 'Australia/Sydney'),
+	('IDR663', 'Brisbane (Mt Stapylton)', 'QLD', -27.7178, 153.2400, 'Australia/Brisbane'),
+	('IDR503', 'Brisbane (Marburg)', 'QLD', -27.6080, 152.5390, 'Australia/Brisbane'),
+	('IDR083', 'Gympie (Mt Kanigan)', 'QLD', -25.9574, 152.5770, 'Australia/Brisbane'),
+	('IDR233', 'Gladstone', 'QLD', -23.8553, 151.2627, 'Australia/Brisbane'),
+	('IDR723', 'Emerald', 'QLD', -23.5498, 148.2392, 'Australia/Brisbane'),
+	('IDR223', 'Mackay', 'QLD', -21.1170, 149.2170, 'Australia/Brisbane'),
+	('IDR243', 'Bowen', 'QLD', -19.8800, 148.0750, 'Australia/Brisbane'),
+	('IDR733', 'Townsville (Hervey Range)', 'QLD', -19.4198, 146.5509, 'Australia/Brisbane'),
+	('IDR193', 'Cairns', 'QLD', -16.8180, 145.6830, 'Australia/Brisbane'),
+	('IDR413', 'Willis Island', 'QLD', -16.2880, 149.9650, 'Australia/Brisbane'),
+	('IDR363', 'Gulf of Carpentaria (Mornington Is)', 'QLD', -16.6660, 139.1670, 'Australia/Brisbane'),
+	('IDR753', 'Mount Isa', 'QLD', -20.7114, 139.5553, 'Australia/Brisbane'),
+	('IDR563', 'Longreach', 'QLD', -23.4300, 144.2900, 'Australia/Brisbane'),
+	('IDR983', 'Taroom', 'QLD', -25.6960, 149.8980, 'Australia/Brisbane'),
+	('IDR993', 'Warrego', 'QLD', -26.4400, 147.3490, 'Australia/Brisbane'),
+	('IDR633', 'Darwin (Berrimah)', 'NT', -12.4570, 130.9250, 'Australia/Darwin'),
+	('IDR773', 'Warruwi', 'NT', -11.6485, 133.3800, 'Australia/Darwin'),
+	('IDR093', 'Gove', 'NT', -12.2760, 136.8200, 'Australia/Darwin'),
+	('IDR423', 'Katherine (Tindal)', 'NT', -14.5130, 132.4460, 'Australia/Darwin'),
+	('IDR253', 'Alice Springs', 'NT', -23.7951, 133.8890, 'Australia/Darwin'),
+	('IDR763', 'Hobart (Mt Koonya)', 'TAS', -43.1122, 147.8057, 'Australia/Hobart'),
+	('IDR373', 'Hobart Airport', 'TAS', -42.8370, 147.5010, 'Australia/Hobart'),
+	('IDR523', 'N.W. Tasmania (West Takone)', 'TAS', -41.1810, 145.5790, 'Australia/Hobart'),
+	('IDR793', 'Watheroo', 'WA', -30.3600, 116.2890, 'Australia/Perth'),
+	('IDR583', 'South Doodlakine', 'WA', -31.7770, 117.9530, 'Australia/Perth'),
+	('IDR383', 'Newdegate', 'WA', -33.0970, 119.0090, 'Australia/Perth'),
+	('IDR313', 'Albany', 'WA', -34.9418, 117.8163, 'Australia/Perth'),
+	('IDR323', 'Esperance', 'WA', -33.8300, 121.8920, 'Australia/Perth'),
+	('IDR483', 'Kalgoorlie', 'WA', -30.7840, 121.4550, 'Australia/Perth'),
+	('IDR443', 'Giles', 'WA', -25.0300, 128.3000, 'Australia/Perth'),
+	('IDR063', 'Geraldton', 'WA', -28.8040, 114.6970, 'Australia/Perth'),
+	('IDR053', 'Carnarvon', 'WA', -24.8880, 113.6700, 'Australia/Perth'),
+	('IDR293', 'Learmonth', 'WA', -22.1030, 113.9990, 'Australia/Perth'),
+	('IDR153', 'Dampier', 'WA', -20.6450, 116.7530, 'Australia/Perth'),
+	('IDR163', 'Port Hedland', 'WA', -20.3720, 118.6320, 'Australia/Perth'),
+	('IDR173', 'Broome', 'WA', -17.9480, 122.2350, 'Australia/Perth'),
+	('IDR393', 'Halls Creek', 'WA', -18.2290, 127.6630, 'Australia/Perth'),
+	('IDR073', 'Wyndham', 'WA', -15.4530, 128.1190, 'Australia/Perth')
+ON CONFLICT (bom_radar_id) DO NOTHING;
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- every range a site publishes, range_km is the one shown by default
+ALTER TABLE locations ADD COLUMN ranges INTEGER[] NOT NULL DEFAULT '{64,128,256,512}';
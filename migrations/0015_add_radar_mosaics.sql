@@ -0,0 +1,26 @@
+-- Add migration script here
+-- composites of many radars, regions without their own product are cropped
+-- out of another with the crop given as fractions of the frame
+CREATE TABLE radar_mosaics (
+	region TEXT PRIMARY KEY,
+	name TEXT NOT NULL,
+	bom_radar_id TEXT NOT NULL,
+	layers TEXT[] NOT NULL DEFAULT '{background,locations}',
+	time_zone TEXT NOT NULL DEFAULT 'Australia/Sydney',
+	crop_left DOUBLE PRECISION,
+	crop_top DOUBLE PRECISION,
+	crop_right DOUBLE PRECISION,
+	crop_bottom DOUBLE PRECISION,
+	created_at TIMESTAMP WITHOUT TIME ZONE DEFAULT now()
+);
+
+INSERT INTO radar_mosaics (region, name, bom_radar_id) VALUES ('AU', 'Australia', 'IDR00004');
+
+INSERT INTO radar_mosaics (region, name, bom_radar_id, time_zone, crop_left, crop_top, crop_right, crop_bottom) VALUES
+	('WA', 'Western Australia', 'IDR00004', 'Australia/Perth', 0.04, 0.13, 0.42, 0.74),
+	('NT', 'Northern Territory', 'IDR00004', 'Australia/Darwin', 0.40, 0.07, 0.62, 0.49),
+	('SA', 'South Australia', 'IDR00004', 'Australia/Adelaide', 0.40, 0.46, 0.68, 0.80),
+	('QLD', 'Queensland', 'IDR00004', 'Australia/Brisbane', 0.60, 0.04, 0.96, 0.57),
+	('NSW', 'New South Wales', 'IDR00004', 'Australia/Sydney', 0.66, 0.53, 0.96, 0.78),
+	('VIC', 'Victoria', 'IDR00004', 'Australia/Melbourne', 0.66, 0.68, 0.88, 0.83),
+	('TAS', 'Tasmania', 'IDR00004', 'Australia/Hobart', 0.73, 0.82, 0.85, 0.95);
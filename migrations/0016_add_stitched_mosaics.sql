@@ -0,0 +1,8 @@
+-- Add migration script here
+-- mosaics with sites are stitched together from those radars instead of
+-- using a bom product, the site with the most recent frame picks the frame times
+ALTER TABLE radar_mosaics ALTER COLUMN bom_radar_id DROP NOT NULL;
+ALTER TABLE radar_mosaics ADD COLUMN sites TEXT[] NOT NULL DEFAULT '{}';
+
+INSERT INTO radar_mosaics (region, name, sites, time_zone) VALUES
+	('SWWA', 'South West WA', '{IDR703,IDR063,IDR793,IDR583,IDR383,IDR313}', 'Australia/Perth');
@@ -0,0 +1,10 @@
+-- Add migration script here
+-- every site has 128, 256 and 512km products, only some have a 64km one
+UPDATE locations SET ranges = '{128,256,512}';
+UPDATE locations SET ranges = '{64,128,256,512}' WHERE bom_radar_id IN
+	('IDR703', 'IDR023', 'IDR713', 'IDR663', 'IDR643', 'IDR633', 'IDR763', 'IDR403');
+UPDATE locations SET ranges = array_append(ranges, range_km) WHERE NOT range_km = ANY(ranges);
+
+-- range_km only repeated the range in bom_radar_id
+ALTER TABLE locations DROP COLUMN range_km;
+ALTER TABLE locations ALTER COLUMN ranges DROP DEFAULT;
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- the capital city radars keep a full day of frames for their timelapses,
+-- the rest of the network only has what the source holds when it's asked for
+UPDATE locations SET priority = 5 WHERE priority = 0 AND bom_radar_id IN
+	('IDR023', 'IDR643', 'IDR713', 'IDR403', 'IDR663', 'IDR633', 'IDR763');
@@ -0,0 +1,232 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, TimeDelta, Timelike, Utc};
+use chrono_tz::Tz;
+use twilight_http::Client as HttpClient;
+use twilight_model::id::Id;
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder};
+
+use crate::{
+    bom::{self, BOMError},
+    radar::RadarSite,
+    rainfall::{self, RainfallGrid},
+};
+
+/// Frames older than this are not used for alerts, the radar is probably down.
+const MAX_FRAME_AGE_MINUTES: i64 = 30;
+
+/// Strongest rain within `radius_km` of a point, with its distance in km.
+fn strongest_rain_near(
+    site: &RadarSite,
+    grid: &RainfallGrid,
+    latitude: f64,
+    longitude: f64,
+    radius_km: f64,
+) -> Option<(f32, f64)> {
+    let km_per_pixel = site.km_per_pixel()?;
+    let (centre_x, centre_y) = site.pixel_for(latitude, longitude)?;
+    let radius = (radius_km / km_per_pixel).ceil() as i64;
+
+    let mut strongest: Option<(f32, f64)> = None;
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let distance = (dx as f64).hypot(dy as f64) * km_per_pixel;
+            let (x, y) = (centre_x as i64 + dx, centre_y as i64 + dy);
+            if distance > radius_km || x < 0 || y < 0 {
+                continue;
+            }
+
+            let Some(rate) = grid.rate_at(x as u32, y as u32).filter(|r| *r > 0.0) else {
+                continue;
+            };
+
+            strongest = match strongest {
+                Some((best, best_distance))
+                    if best > rate || (best == rate && best_distance <= distance) =>
+                {
+                    Some((best, best_distance))
+                }
+                _ => Some((rate, distance)),
+            };
+        }
+    }
+
+    strongest
+}
+
+/// Quiet hours can wrap past midnight, e.g. 22 until 7.
+fn in_quiet_hours(start: Option<i32>, end: Option<i32>, time_zone: Tz, now: DateTime<Utc>) -> bool {
+    let (Some(start), Some(end)) = (start, end) else {
+        return false;
+    };
+
+    let hour = now.with_timezone(&time_zone).hour() as i32;
+    if start <= end {
+        (start..end).contains(&hour)
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+async fn latest_rainfall(
+    bom: &bom::BOM,
+    bom_id: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<(RadarSite, RainfallGrid)>, BOMError> {
+    let site = bom.get_radar_site(bom_id).await?;
+    let (time, grid) = bom.get_latest_rainfall_for(bom_id).await?;
+
+    if now - time > TimeDelta::minutes(MAX_FRAME_AGE_MINUTES) {
+        tracing::warn!("latest frame for {bom_id} is from {time}, skipping alerts");
+        return Ok(None);
+    }
+
+    Ok(Some((site, grid)))
+}
+
+async fn send_alert(
+    http: &HttpClient,
+    user_id: i64,
+    site: &RadarSite,
+    rate: f32,
+    distance_km: f64,
+) -> Result<(), anyhow::Error> {
+    let channel = http
+        .create_private_channel(Id::new(user_id as u64))
+        .await?
+        .model()
+        .await?;
+
+    let embed = EmbedBuilder::new()
+        .title("🌧️ Rain nearby")
+        .description(format!(
+            "{} rain, around {rate} mm/h, {distance_km:.0} km from your alert point",
+            rainfall::intensity(rate)
+        ))
+        .color(0x003366)
+        .footer(EmbedFooterBuilder::new(format!("from {}", site.name)))
+        .build();
+
+    http.create_message(channel.id).embeds(&[embed]).await?;
+
+    Ok(())
+}
+
+/// Checks every subscription against the latest frame of its radar and sends
+/// a dm when rain over the threshold is inside the radius.
+pub async fn send_rain_alerts(
+    bom: Arc<bom::BOM>,
+    http: Arc<HttpClient>,
+) -> Result<(), anyhow::Error> {
+    let subscriptions = sqlx::query!("SELECT * FROM rain_alerts")
+        .fetch_all(bom.db())
+        .await?;
+
+    let now = Utc::now();
+    let mut radars: HashMap<String, Option<(RadarSite, RainfallGrid)>> = HashMap::new();
+
+    for subscription in subscriptions {
+        let cooldown = TimeDelta::minutes(subscription.cooldown_minutes as i64);
+        if let Some(last_alerted_at) = subscription.last_alerted_at {
+            if now - last_alerted_at.and_utc() < cooldown {
+                continue;
+            }
+        }
+
+        let time_zone = subscription.time_zone.parse().unwrap_or(Tz::UTC);
+        if in_quiet_hours(
+            subscription.quiet_start_hour,
+            subscription.quiet_end_hour,
+            time_zone,
+            now,
+        ) {
+            continue;
+        }
+
+        if !radars.contains_key(&subscription.bom_radar_id) {
+            let latest = latest_rainfall(&bom, &subscription.bom_radar_id, now)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::error!("rainfall for {} failed: {e}", subscription.bom_radar_id);
+                    None
+                });
+
+            radars.insert(subscription.bom_radar_id.clone(), latest);
+        }
+
+        let Some((site, grid)) = radars
+            .get(&subscription.bom_radar_id)
+            .and_then(Option::as_ref)
+        else {
+            continue;
+        };
+
+        let Some((rate, distance_km)) = strongest_rain_near(
+            site,
+            grid,
+            subscription.latitude,
+            subscription.longitude,
+            subscription.radius_km,
+        )
+        .filter(|(rate, _)| *rate as f64 >= subscription.threshold) else {
+            continue;
+        };
+
+        tracing::info!(
+            "sending rain alert {} to {}",
+            subscription.id,
+            subscription.user_id
+        );
+
+        if let Err(e) = send_alert(&http, subscription.user_id, site, rate, distance_km).await {
+            tracing::error!("error sending rain alert {}: {e}", subscription.id);
+            continue;
+        }
+
+        sqlx::query!(
+            "UPDATE rain_alerts SET last_alerted_at = ($1) WHERE id = ($2)",
+            now.naive_utc(),
+            subscription.id
+        )
+        .execute(bom.db())
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn perth(hour: u32, minute: u32) -> DateTime<Utc> {
+        Tz::Australia__Perth
+            .with_ymd_and_hms(2025, 4, 14, hour, minute, 0)
+            .unwrap()
+            .to_utc()
+    }
+
+    #[rstest]
+    #[case(Some(22), Some(7), perth(23, 0), true)]
+    #[case(Some(22), Some(7), perth(6, 59), true)]
+    #[case(Some(22), Some(7), perth(7, 0), false)]
+    #[case(Some(22), Some(7), perth(12, 0), false)]
+    #[case(Some(9), Some(17), perth(12, 0), true)]
+    #[case(Some(9), Some(17), perth(17, 0), false)]
+    #[case(Some(22), None, perth(23, 0), false)]
+    #[case(None, None, perth(23, 0), false)]
+    fn quiet_hours(
+        #[case] start: Option<i32>,
+        #[case] end: Option<i32>,
+        #[case] now: DateTime<Utc>,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(
+            in_quiet_hours(start, end, Tz::Australia__Perth, now),
+            expected
+        );
+    }
+}
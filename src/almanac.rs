@@ -0,0 +1,115 @@
+use chrono::{DateTime, FixedOffset};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+
+use crate::{
+    forecast::{format_day, parse_time_zone, ForecastError, Provider},
+    types::{GraphDay, WillyWeatherForecast},
+    willyweather::parse_date_time,
+};
+
+pub fn moon_phase_emoji(phase: &str) -> &'static str {
+    match phase.to_lowercase().as_str() {
+        "new moon" => "🌑",
+        "waxing crescent" => "🌒",
+        "first quarter" => "🌓",
+        "waxing gibbous" => "🌔",
+        "full moon" => "🌕",
+        "waning gibbous" => "🌖",
+        "last quarter" | "third quarter" => "🌗",
+        "waning crescent" => "🌘",
+        _ => "🌙",
+    }
+}
+
+/// Adds a field per day, skipping days without any entries.
+fn day_fields<T>(
+    mut embed: EmbedBuilder,
+    days: Vec<GraphDay<T>>,
+    forecast: &WillyWeatherForecast,
+    describe: impl Fn(&[T]) -> Result<String, ForecastError>,
+) -> Result<EmbedBuilder, ForecastError> {
+    let time_zone = parse_time_zone(&forecast.location.time_zone)?;
+
+    for day in days.into_iter().filter(|day| !day.entries.is_empty()) {
+        let date = parse_date_time(&day.date_time, time_zone)?;
+        embed =
+            embed.field(EmbedFieldBuilder::new(format_day(&date), describe(&day.entries)?).build());
+    }
+
+    Ok(embed)
+}
+
+fn local_time(
+    forecast: &WillyWeatherForecast,
+    date_time: &str,
+) -> Result<DateTime<FixedOffset>, ForecastError> {
+    parse_date_time(date_time, parse_time_zone(&forecast.location.time_zone)?)
+}
+
+pub fn sun_embed(mut forecast: WillyWeatherForecast) -> Result<EmbedBuilder, ForecastError> {
+    let embed = EmbedBuilder::new()
+        .title(format!(
+            "🌅 Sunrise and sunset for {}",
+            forecast.location.name
+        ))
+        .color(0x003366)
+        .footer(EmbedFooterBuilder::new(Provider::WillyWeather.footer()));
+
+    let days = std::mem::take(&mut forecast.forecasts.sunrisesunset.days);
+    day_fields(embed, days, &forecast, |entries| {
+        let sun = &entries[0];
+        Ok(format!(
+            "**First light:** {}, **Sunrise:** {}, **Sunset:** {}, **Last light:** {}",
+            local_time(&forecast, &sun.first_light_date_time)?.format("%H:%M"),
+            local_time(&forecast, &sun.rise_date_time)?.format("%H:%M"),
+            local_time(&forecast, &sun.set_date_time)?.format("%H:%M"),
+            local_time(&forecast, &sun.last_light_date_time)?.format("%H:%M"),
+        ))
+    })
+}
+
+pub fn tides_embed(mut forecast: WillyWeatherForecast) -> Result<EmbedBuilder, ForecastError> {
+    let mut embed = EmbedBuilder::new()
+        .title(format!("🌊 Tides for {}", forecast.location.name))
+        .color(0x003366)
+        .footer(EmbedFooterBuilder::new(Provider::WillyWeather.footer()));
+
+    let days = std::mem::take(&mut forecast.forecasts.tides.days);
+    if days.iter().all(|day| day.entries.is_empty()) {
+        embed = embed.description("There's no tide station near here");
+    }
+
+    day_fields(embed, days, &forecast, |entries| {
+        let tides = entries
+            .iter()
+            .map(|tide| {
+                let arrow = if tide.kind == "high" { "🔼" } else { "🔽" };
+                Ok(format!(
+                    "{arrow} {} {:.2} m",
+                    local_time(&forecast, &tide.date_time)?.format("%H:%M"),
+                    tide.height
+                ))
+            })
+            .collect::<Result<Vec<_>, ForecastError>>()?;
+
+        Ok(tides.join(", "))
+    })
+}
+
+pub fn moon_embed(mut forecast: WillyWeatherForecast) -> Result<EmbedBuilder, ForecastError> {
+    let embed = EmbedBuilder::new()
+        .title(format!("🌙 Moon phases for {}", forecast.location.name))
+        .color(0x003366)
+        .footer(EmbedFooterBuilder::new(Provider::WillyWeather.footer()));
+
+    let days = std::mem::take(&mut forecast.forecasts.moonphases.days);
+    day_fields(embed, days, &forecast, |entries| {
+        let moon = &entries[0];
+        Ok(format!(
+            "{} {}, {:.0}% illuminated",
+            moon_phase_emoji(&moon.phase),
+            moon.phase,
+            moon.percentage_illuminated
+        ))
+    })
+}
@@ -1,14 +1,40 @@
-use async_ftp::{FtpError, FtpStream};
-use image::{codecs::gif::GifEncoder, imageops, Delay, DynamicImage, GenericImageView};
-use s3::error::S3Error;
+use crate::{
+    composite::Composite,
+    mosaic::{Crop, Mosaic, MosaicSource},
+    nowcast::{self, Motion},
+    overlay,
+    radar::{self, PointRainfall, RadarSite},
+    rainfall::{self, RainfallGrid},
+    render::{self, OutputFormat, RenderError, RenderOptions},
+    source::{ImageSource, SourceError},
+    storage::{ObjectStore, StorageError},
+};
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use image::{imageops, DynamicImage, GenericImageView};
+use regex::Regex;
 use sqlx::PgPool;
-use std::path::Path;
-use tokio::io::AsyncReadExt;
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, LazyLock, Mutex},
+};
+use tokio::sync::OnceCell;
+
+/// Motion estimated from a radar's frames up to the frame captured at the time.
+type CachedMotion = (DateTime<Utc>, Arc<OnceCell<Option<Motion>>>);
+/// A stitched mosaic's grid and the sites it was built for.
+type CachedComposite = (Vec<String>, Arc<OnceCell<Arc<Composite>>>);
 
 #[allow(clippy::upper_case_acronyms)]
 pub struct BOM {
-    bucket: Box<s3::Bucket>,
+    source: Box<dyn ImageSource>,
+    bucket: Box<dyn ObjectStore>,
     db: PgPool,
+    /// Block matching is slow, so it's done once per radar per new frame.
+    motions: Mutex<HashMap<String, CachedMotion>>,
+    /// Stitched mosaic grids by region, they only change with the sites.
+    composites: Mutex<HashMap<String, CachedComposite>>,
 }
 
 const FILE_TYPES_TO_MERGE: [&str; 4] = ["background", "topography", "locations", "range"];
@@ -18,15 +44,55 @@ const SATELLITE_DATA_PATH: &str = "/anon/gen/gms";
 pub const RADAR_CACHE_PATH: &str = "radar_cache";
 pub const SATELLITE_CACHE_PATH: &str = "satellite_cache";
 
+/// Furthest a site's frame can be from a stitched frame's time and still be
+/// used for it, sites scan every 6 minutes but not in step.
+const STITCH_WINDOW_MINUTES: i64 = 5;
+
 const IMAGE_HOST: &str = "https://bom-images.anurag.sh";
 
+static MATCH_RADAR_FILENAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^IDR\d{3}(\d{2})?\.T\.(?<datetime>\d{12})\.png"#).unwrap());
+static MATCH_SATELLITE_FILENAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^IDE\d{5}\.(?<datetime>\d{12})\.jpg"#).unwrap());
+
+fn frame_time(matcher: &Regex, path: &str) -> Option<DateTime<Utc>> {
+    let basename = Path::new(path).file_name()?.to_str()?;
+    let caps = matcher.captures(basename)?;
+
+    // 2025 04 14 12 04
+    NaiveDateTime::parse_from_str(&caps["datetime"], "%Y%m%d%H%M")
+        .ok()
+        .map(|datetime| datetime.and_utc())
+}
+
+/// Time a radar frame was captured, from a filename like `IDR703.T.202504141204.png`
+/// or `IDR00004.T.202504141204.png` for mosaics.
+pub fn radar_frame_time(path: &str) -> Option<DateTime<Utc>> {
+    frame_time(&MATCH_RADAR_FILENAME, path)
+}
+
+/// Time a satellite frame was captured, from a filename like `IDE00416.202504141200.jpg`.
+pub fn satellite_frame_time(path: &str) -> Option<DateTime<Utc>> {
+    frame_time(&MATCH_SATELLITE_FILENAME, path)
+}
+
+fn frame_label(time: Option<DateTime<Utc>>, time_zone: Tz) -> String {
+    match time {
+        Some(time) => time
+            .with_timezone(&time_zone)
+            .format("%a %d %b %H:%M %Z")
+            .to_string(),
+        None => "unknown time".to_owned(),
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum BOMError {
     #[error("an unspecified internal error occurred: {0}")]
     Unknown(#[from] anyhow::Error),
 
-    #[error("a ftp response error occurred: {0}")]
-    Ftp(#[from] async_ftp::FtpError),
+    #[error("a image source error occurred: {0}")]
+    Source(#[from] SourceError),
 
     #[error("a sql error occurred: {0}")]
     Sql(#[from] sqlx::Error),
@@ -37,8 +103,8 @@ pub enum BOMError {
     #[error("an io error occurred: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("a s3 error occurred: {0}")]
-    S3(#[from] S3Error),
+    #[error("a storage error occurred: {0}")]
+    Storage(#[from] StorageError),
 
     #[error("a jpg compression error occurred: {0}")]
     JpgCompression(#[from] turbojpeg::Error),
@@ -46,113 +112,282 @@ pub enum BOMError {
     #[error("a regex error occurred: {0}")]
     Regex(#[from] regex::Error),
 
+    #[error("a render error occurred: {0}")]
+    Render(#[from] RenderError),
+
     #[error("a task join error error occurred: {0}")]
     TaskJoin(#[from] tokio::task::JoinError),
+
+    #[error("no radar frames are available for {0}")]
+    NoRadarFrames(String),
+
+    #[error("unknown radar: {0}")]
+    UnknownRadar(String),
+
+    #[error("no radar covers {latitude}, {longitude}")]
+    OutOfRange { latitude: f64, longitude: f64 },
+
+    #[error("unknown radar mosaic: {0}")]
+    UnknownMosaic(String),
+
+    #[error("{radar} doesn't have a {range_km}km product")]
+    UnsupportedRange { radar: String, range_km: i32 },
 }
 
 impl BOM {
-    async fn get_ftp_client_session() -> Result<FtpStream, FtpError> {
-        let addr = ("ftp.bom.gov.au", 21);
-        let mut ftp_client = FtpStream::connect(addr).await?;
-        ftp_client.login("anonymous", "anonymous").await?;
-        Ok(ftp_client)
-    }
-
-    pub async fn new(bucket: Box<s3::Bucket>, db: PgPool) -> Result<Self, BOMError> {
-        Ok(Self { bucket, db })
+    pub async fn new(
+        source: Box<dyn ImageSource>,
+        bucket: Box<dyn ObjectStore>,
+        db: PgPool,
+    ) -> Result<Self, BOMError> {
+        Ok(Self {
+            source,
+            bucket,
+            db,
+            motions: Default::default(),
+            composites: Default::default(),
+        })
     }
 
     pub fn db(&self) -> &PgPool {
         &self.db
     }
 
-    pub fn bucket(&self) -> &s3::Bucket {
-        &self.bucket
+    pub fn bucket(&self) -> &dyn ObjectStore {
+        self.bucket.as_ref()
+    }
+
+    pub fn source(&self) -> &dyn ImageSource {
+        self.source.as_ref()
+    }
+
+    /// Looked up by site so every range of a registered radar has one.
+    pub async fn radar_time_zone(&self, bom_id: &str) -> Result<Tz, BOMError> {
+        let time_zone = sqlx::query_scalar!(
+            "SELECT time_zone FROM locations WHERE left(bom_radar_id, 5) = left($1, 5) LIMIT 1",
+            bom_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(time_zone
+            .and_then(|time_zone| time_zone.parse().ok())
+            .unwrap_or(Tz::UTC))
+    }
+
+    /// Satellite products cover more than one time zone, each one picks the
+    /// zone its frames are labelled in.
+    pub async fn satellite_time_zone(&self, bom_id: &str) -> Result<Tz, BOMError> {
+        let time_zone = sqlx::query_scalar!(
+            "SELECT time_zone FROM satellites WHERE bom_satellite_id = ($1)",
+            bom_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(time_zone
+            .and_then(|time_zone| time_zone.parse().ok())
+            .unwrap_or(Tz::UTC))
     }
 
     pub async fn generate_radar_backgrounds(&self) -> Result<(), BOMError> {
-        let locations = sqlx::query!("SELECT * FROM locations")
+        let locations = sqlx::query!("SELECT name, bom_radar_id FROM locations WHERE active")
             .fetch_all(&self.db)
             .await?;
 
         tracing::info!("pre-generating radar backgrounds");
 
-        let mut ftp_client = Self::get_ftp_client_session().await?;
+        // other ranges are generated when they're first asked for
         for location in locations {
             tracing::info!("generating background for {}", location.name);
-            let bom_id = location.bom_radar_id;
-            let mut files = Vec::with_capacity(FILE_TYPES_TO_MERGE.len());
-
-            for file_type in FILE_TYPES_TO_MERGE {
-                let file_to_fetch = format!("{RADAR_BACKGROUND_PATH}/{bom_id}.{file_type}.png");
-                tracing::info!("fetching {file_to_fetch}");
-                let img = self
-                    .get_or_fetch_image(
-                        RADAR_CACHE_PATH,
-                        &file_to_fetch,
-                        "image/png",
-                        &mut ftp_client,
-                    )
-                    .await?;
-                files.push(img);
+            // one site failing shouldn't stop the others
+            if let Err(e) = self.generate_radar_background(&location.bom_radar_id).await {
+                tracing::error!("background for {} failed: {e}", location.bom_radar_id);
             }
+        }
 
-            // get rain legend (our base image)
-            let file_to_fetch = format!("{RADAR_BACKGROUND_PATH}/IDR.legend.0.png");
-            tracing::info!("fetching {file_to_fetch}");
-            let mut rain_legend = self
-                .get_or_fetch_image(
-                    RADAR_CACHE_PATH,
-                    &file_to_fetch,
-                    "image/png",
-                    &mut ftp_client,
-                )
-                .await?;
+        for (region, _) in Mosaic::regions(&self.db).await? {
+            tracing::info!("generating background for the {region} mosaic");
+            let mosaic = Mosaic::get(&self.db, &region).await?;
+            if let Err(e) = self.generate_mosaic_background(&mosaic).await {
+                tracing::error!("background for the {region} mosaic failed: {e}");
+            }
+        }
+
+        Ok(())
+    }
 
-            for top in files {
-                imageops::overlay(&mut rain_legend, &top, 0, 0);
+    /// Backgrounds are only generated up front for each site's default range.
+    async fn ensure_radar_background(&self, bom_id: &str) -> Result<(), BOMError> {
+        if !self.bucket.exists(&format!("{bom_id}.base.png")).await? {
+            self.generate_radar_background(bom_id).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn generate_radar_background(&self, bom_id: &str) -> Result<(), BOMError> {
+        let background = self.merge_background(bom_id, &FILE_TYPES_TO_MERGE).await?;
+        self.put_background(&format!("{}.base.png", bom_id), &background)
+            .await
+    }
+
+    /// Mosaic products have their own layers and are cropped to their region,
+    /// stitched mosaics stitch their sites' backgrounds.
+    pub async fn generate_mosaic_background(&self, mosaic: &Mosaic) -> Result<(), BOMError> {
+        let background = match &mosaic.source {
+            MosaicSource::Product {
+                bom_radar_id,
+                layers,
+                crop,
+            } => {
+                let layers = layers.iter().map(String::as_str).collect::<Vec<_>>();
+                let background = self.merge_background(bom_radar_id, &layers).await?;
+                match crop {
+                    Some(crop) => crop.apply(&background),
+                    None => background,
+                }
             }
+            MosaicSource::Stitched { sites } => {
+                let (sites, composite) = self.composite_for(&mosaic.region, sites).await?;
 
-            let mut bytes = Vec::new();
-            rain_legend.write_to(
-                &mut std::io::Cursor::new(&mut bytes),
-                image::ImageFormat::Png,
-            )?;
+                let mut backgrounds = Vec::with_capacity(sites.len());
+                for site in &sites {
+                    backgrounds.push(self.radar_background(&site.bom_radar_id).await.ok());
+                }
 
-            let path = format!("{}.base.png", bom_id);
-            self.bucket
-                .put_object_with_content_type(path, bytes.as_ref(), "image/png")
-                .await?;
+                let rt = tokio::runtime::Handle::current();
+                rt.spawn_blocking(move || composite.stitch_background(&backgrounds))
+                    .await?
+            }
+        };
+
+        self.put_background(&mosaic.background_path(), &background)
+            .await
+    }
+
+    /// Sites that aren't registered are left out of the composite. The grid
+    /// is only built again when the region's sites change.
+    async fn composite_for(
+        &self,
+        region: &str,
+        ids: &[String],
+    ) -> Result<(Vec<RadarSite>, Arc<Composite>), BOMError> {
+        let registered = self.radar_sites().await?;
+        let sites = ids
+            .iter()
+            .filter_map(|id| registered.iter().find(|site| site.bom_radar_id == *id))
+            .cloned()
+            .collect::<Vec<_>>();
+        let site_ids = sites
+            .iter()
+            .map(|site| site.bom_radar_id.clone())
+            .collect::<Vec<_>>();
+
+        let cell = {
+            let mut composites = self.composites.lock().unwrap();
+            match composites.get(region) {
+                Some((built_for, cell)) if *built_for == site_ids => cell.clone(),
+                _ => {
+                    let cell = Arc::new(OnceCell::new());
+                    composites.insert(region.to_owned(), (site_ids, cell.clone()));
+                    cell
+                }
+            }
+        };
+
+        let composite = cell
+            .get_or_try_init(|| async {
+                let rt = tokio::runtime::Handle::current();
+                let sites = sites.clone();
+                rt.spawn_blocking(move || Composite::new(&sites))
+                    .await?
+                    .map(Arc::new)
+                    .ok_or_else(|| BOMError::UnknownRadar(ids.join(", ")))
+            })
+            .await?
+            .clone();
+
+        Ok((sites, composite))
+    }
+
+    async fn radar_background(&self, bom_id: &str) -> Result<DynamicImage, BOMError> {
+        self.ensure_radar_background(bom_id).await?;
+        let bytes = self.bucket.get(&format!("{bom_id}.base.png")).await?;
+
+        Ok(image::ImageReader::new(std::io::Cursor::new(bytes))
+            .with_guessed_format()?
+            .decode()?)
+    }
+
+    pub async fn ensure_mosaic_background(&self, mosaic: &Mosaic) -> Result<(), BOMError> {
+        if !self.bucket.exists(&mosaic.background_path()).await? {
+            self.generate_mosaic_background(mosaic).await?;
         }
 
         Ok(())
     }
 
-    async fn fetch_image(
+    /// Layers the product's transparencies over the rain legend.
+    async fn merge_background(
         &self,
-        path: &str,
-        mime: &str,
-        ftp_client: &mut FtpStream,
-    ) -> Result<(), BOMError> {
+        bom_id: &str,
+        layers: &[&str],
+    ) -> Result<DynamicImage, BOMError> {
+        let mut files = Vec::with_capacity(layers.len());
+
+        for file_type in layers {
+            let file_to_fetch = format!("{RADAR_BACKGROUND_PATH}/{bom_id}.{file_type}.png");
+            tracing::info!("fetching {file_to_fetch}");
+            let img = self
+                .get_or_fetch_image(RADAR_CACHE_PATH, &file_to_fetch, "image/png")
+                .await?;
+            files.push(img);
+        }
+
+        // get rain legend (our base image)
+        let file_to_fetch = format!("{RADAR_BACKGROUND_PATH}/IDR.legend.0.png");
+        tracing::info!("fetching {file_to_fetch}");
+        let mut rain_legend = self
+            .get_or_fetch_image(RADAR_CACHE_PATH, &file_to_fetch, "image/png")
+            .await?;
+
+        let missing = rainfall::missing_from_legend(&rain_legend);
+        if !missing.is_empty() {
+            tracing::error!("rain legend is missing the colours for {missing:?} mm/h");
+        }
+
+        for top in files {
+            imageops::overlay(&mut rain_legend, &top, 0, 0);
+        }
+
+        Ok(rain_legend)
+    }
+
+    async fn put_background(&self, path: &str, background: &DynamicImage) -> Result<(), BOMError> {
+        let mut bytes = Vec::new();
+        background.write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )?;
+
+        self.bucket.put(path, &bytes, "image/png").await?;
+
+        Ok(())
+    }
+
+    async fn fetch_image(&self, path: &str, mime: &str) -> Result<(), BOMError> {
         let path_obj = Path::new(path);
         let basename = path_obj.file_name().unwrap().to_str().unwrap();
 
         let cache_path = format!("{RADAR_CACHE_PATH}/{basename}");
-        let head_result = self.bucket.head_object(&cache_path).await;
-        let is_missing = head_result.is_err();
+        let is_missing = !self.bucket.exists(&cache_path).await?;
 
         if is_missing {
             tracing::info!("downloading {path}");
-            let mut buffer = Vec::new();
-            let _size = ftp_client
-                .simple_retr(path)
-                .await?
-                .read_to_end(&mut buffer)
-                .await?;
+            let buffer = self.source.retrieve(path).await?;
 
-            self.bucket
-                .put_object_with_content_type(cache_path, &buffer, mime)
-                .await?;
+            self.bucket.put(&cache_path, &buffer, mime).await?;
         }
 
         Ok(())
@@ -162,23 +397,16 @@ impl BOM {
         &self,
         path: &str,
         mime: &str,
-        ftp_client: &mut FtpStream,
     ) -> Result<DynamicImage, BOMError> {
         let path_obj = Path::new(path);
         let basename = path_obj.file_name().unwrap().to_str().unwrap();
 
         let cache_path = format!("{SATELLITE_CACHE_PATH}/{basename}");
-        let head_result = self.bucket.head_object(&cache_path).await;
-        let is_missing = head_result.is_err();
+        let is_missing = !self.bucket.exists(&cache_path).await?;
 
         if is_missing {
             tracing::info!("downloading {path}");
-            let mut buffer = Vec::new();
-            let _size = ftp_client
-                .simple_retr(path)
-                .await?
-                .read_to_end(&mut buffer)
-                .await?;
+            let buffer = self.source.retrieve(path).await?;
 
             let img = image::ImageReader::new(std::io::Cursor::new(buffer))
                 .with_guessed_format()?
@@ -186,17 +414,14 @@ impl BOM {
 
             let bytes = Self::compress_jpg(img).await?;
 
-            self.bucket
-                .put_object_with_content_type(cache_path, &bytes, mime)
-                .await?;
+            self.bucket.put(&cache_path, &bytes, mime).await?;
 
             Ok(image::ImageReader::new(std::io::Cursor::new(bytes))
                 .with_guessed_format()?
                 .decode()?)
         } else {
             tracing::info!("already exists in s3 {path}");
-            let file = self.bucket.get_object(cache_path).await?;
-            let file = file.into_bytes().to_vec();
+            let file = self.bucket.get(&cache_path).await?;
 
             let img = image::ImageReader::new(std::io::Cursor::new(file))
                 .with_guessed_format()?
@@ -229,27 +454,16 @@ impl BOM {
         .await?
     }
 
-    async fn fetch_compressed_and_resized(
-        &self,
-        path: &str,
-        mime: &str,
-        ftp_client: &mut FtpStream,
-    ) -> Result<(), BOMError> {
+    async fn fetch_compressed_and_resized(&self, path: &str, mime: &str) -> Result<(), BOMError> {
         let path_obj = Path::new(path);
         let basename = path_obj.file_name().unwrap().to_str().unwrap();
 
         let cache_path = format!("{SATELLITE_CACHE_PATH}/{basename}");
-        let head_result = self.bucket.head_object(&cache_path).await;
-        let is_missing = head_result.is_err();
+        let is_missing = !self.bucket.exists(&cache_path).await?;
 
         if is_missing {
             tracing::info!("downloading {path}");
-            let mut buffer = Vec::new();
-            let _size = ftp_client
-                .simple_retr(path)
-                .await?
-                .read_to_end(&mut buffer)
-                .await?;
+            let buffer = self.source.retrieve(path).await?;
 
             let img = image::ImageReader::new(std::io::Cursor::new(buffer))
                 .with_guessed_format()?
@@ -257,9 +471,7 @@ impl BOM {
 
             let bytes = Self::compress_jpg(img).await?;
 
-            self.bucket
-                .put_object_with_content_type(cache_path, &bytes, mime)
-                .await?;
+            self.bucket.put(&cache_path, &bytes, mime).await?;
         }
 
         Ok(())
@@ -272,8 +484,7 @@ impl BOM {
         let cache_path = format!("{cache_path}/{basename}");
 
         tracing::info!("fetching image from s3: {path}");
-        let file = self.bucket.get_object(cache_path).await?;
-        let file = file.into_bytes().to_vec();
+        let file = self.bucket.get(&cache_path).await?;
 
         let img = image::ImageReader::new(std::io::Cursor::new(file))
             .with_guessed_format()?
@@ -287,27 +498,18 @@ impl BOM {
         cache_path: &str,
         path: &str,
         mime: &str,
-        ftp_client: &mut FtpStream,
     ) -> Result<DynamicImage, BOMError> {
         let path_obj = Path::new(path);
         let basename = path_obj.file_name().unwrap().to_str().unwrap();
 
         let cache_path = format!("{cache_path}/{basename}");
-        let head_result = self.bucket.head_object(&cache_path).await;
-        let is_missing = head_result.is_err();
+        let is_missing = !self.bucket.exists(&cache_path).await?;
 
         if is_missing {
             tracing::info!("downloading {path}");
-            let mut buffer = Vec::new();
-            let _size = ftp_client
-                .simple_retr(path)
-                .await?
-                .read_to_end(&mut buffer)
-                .await?;
+            let buffer = self.source.retrieve(path).await?;
 
-            self.bucket
-                .put_object_with_content_type(cache_path, &buffer, mime)
-                .await?;
+            self.bucket.put(&cache_path, &buffer, mime).await?;
 
             let img = image::ImageReader::new(std::io::Cursor::new(buffer))
                 .with_guessed_format()?
@@ -316,8 +518,7 @@ impl BOM {
             Ok(img)
         } else {
             tracing::info!("already exists in s3 {path}");
-            let file = self.bucket.get_object(cache_path).await?;
-            let file = file.into_bytes().to_vec();
+            let file = self.bucket.get(&cache_path).await?;
 
             let img = image::ImageReader::new(std::io::Cursor::new(file))
                 .with_guessed_format()?
@@ -328,71 +529,152 @@ impl BOM {
     }
 
     pub async fn fetch_all_radar_images_for(&self, bom_id: &str) -> Result<(), BOMError> {
-        let mut ftp_client = Self::get_ftp_client_session().await?;
-        let radar_images = ftp_client
-            .nlst(Some(RADAR_DATA_PATH))
+        let radar_images = self
+            .source
+            .list(RADAR_DATA_PATH)
             .await?
             .into_iter()
             .filter(|i| i.starts_with(&format!("{RADAR_DATA_PATH}/{bom_id}")))
             .filter(|i| i.ends_with(".png"));
 
         for file in radar_images {
-            self.fetch_image(&file, "image/png", &mut ftp_client)
-                .await?;
+            self.fetch_image(&file, "image/png").await?;
         }
 
         Ok(())
     }
 
     pub async fn fetch_all_satellite_images_for(&self, bom_id: &str) -> Result<(), BOMError> {
-        let mut ftp_client = Self::get_ftp_client_session().await?;
-        let satellite_images = ftp_client
-            .nlst(Some(SATELLITE_DATA_PATH))
+        let satellite_images = self
+            .source
+            .list(SATELLITE_DATA_PATH)
             .await?
             .into_iter()
             .filter(|i| i.starts_with(&format!("{SATELLITE_DATA_PATH}/{bom_id}")))
             .filter(|i| i.ends_with(".jpg"));
 
         for file in satellite_images {
-            self.fetch_compressed_and_resized(&file, "image/jpg", &mut ftp_client)
+            self.fetch_compressed_and_resized(&file, "image/jpg")
                 .await?;
         }
 
         Ok(())
     }
 
+    fn timelapse_bucket_path(
+        bom_id: &str,
+        options: &RenderOptions,
+        format: OutputFormat,
+    ) -> String {
+        let extension = format.extension();
+        if *options == RenderOptions::TIMELAPSE {
+            format!("external/{}.radar.24h.{extension}", bom_id)
+        } else {
+            format!(
+                "external/{}.{}.radar.24h.{extension}",
+                bom_id,
+                options.cache_key()
+            )
+        }
+    }
+
+    fn satellite_bucket_path(
+        bom_id: &str,
+        options: &RenderOptions,
+        format: OutputFormat,
+    ) -> String {
+        let extension = format.extension();
+        if *options == RenderOptions::SATELLITE {
+            format!("external/{}.latest.satellite.{extension}", bom_id)
+        } else {
+            format!(
+                "external/{}.{}.satellite.{extension}",
+                bom_id,
+                options.cache_key()
+            )
+        }
+    }
+
+    async fn encode(
+        images: Vec<DynamicImage>,
+        options: &RenderOptions,
+        format: OutputFormat,
+    ) -> Result<Vec<u8>, BOMError> {
+        let options = *options;
+        let rt = tokio::runtime::Handle::current();
+
+        let encoded = rt
+            .spawn_blocking(move || render::encode(images, &options, format))
+            .await??;
+
+        Ok(encoded)
+    }
+
+    /// The default gif timelapse of radars in the background refresh is kept
+    /// up to date, anything else is generated on demand.
     pub async fn get_radar_timelapse_24hr_for(
         &self,
         bom_id: &str,
+        options: &RenderOptions,
+        format: OutputFormat,
     ) -> Result<(String, Vec<u8>), BOMError> {
-        let bucket_path = format!("external/{}.radar.24h.gif", bom_id);
+        let refreshed = sqlx::query_scalar!(
+            r#"SELECT EXISTS (
+                SELECT 1 FROM locations WHERE active AND priority > 0 AND bom_radar_id = $1
+            ) AS "refreshed!""#,
+            bom_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        let bucket_path = Self::timelapse_bucket_path(bom_id, options, format);
+        if !refreshed
+            || *options != RenderOptions::TIMELAPSE
+            || format != OutputFormat::Gif
+            || !self.bucket.exists(&bucket_path).await?
+        {
+            return self
+                .generate_radar_timelapse_24hr_for(bom_id, options, format)
+                .await;
+        }
 
-        return Ok((
+        Ok((
             format!("{IMAGE_HOST}/{bucket_path}"),
-            self.bucket().get_object(&bucket_path).await?.to_vec(),
-        ));
+            self.bucket.get(&bucket_path).await?,
+        ))
     }
 
+    /// Same as [`BOM::get_radar_timelapse_24hr_for`] but for satellite loops.
     pub async fn get_latest_satellite_gif_for(
         &self,
         bom_id: &str,
+        options: &RenderOptions,
+        format: OutputFormat,
     ) -> Result<(String, Vec<u8>), BOMError> {
-        let bucket_path = format!("external/{}.latest.satellite.gif", bom_id);
+        if *options != RenderOptions::SATELLITE || format != OutputFormat::Gif {
+            return self
+                .generate_satellite_gif_for(bom_id, options, format)
+                .await;
+        }
 
-        return Ok((
+        let bucket_path = Self::satellite_bucket_path(bom_id, options, format);
+
+        Ok((
             format!("{IMAGE_HOST}/{bucket_path}"),
-            self.bucket().get_object(&bucket_path).await?.to_vec(),
-        ));
+            self.bucket.get(&bucket_path).await?,
+        ))
     }
 
     pub async fn generate_satellite_gif_for(
         &self,
         bom_id: &str,
+        options: &RenderOptions,
+        format: OutputFormat,
     ) -> Result<(String, Vec<u8>), BOMError> {
-        let bucket_path = format!("external/{}.latest.satellite.gif", bom_id);
-        let mut ftp_client = Self::get_ftp_client_session().await?;
-        let mut satellite_images = ftp_client
-            .nlst(Some(SATELLITE_DATA_PATH))
+        let bucket_path = Self::satellite_bucket_path(bom_id, options, format);
+        let mut satellite_images = self
+            .source
+            .list(SATELLITE_DATA_PATH)
             .await?
             .into_iter()
             .filter(|i| i.starts_with(&format!("{SATELLITE_DATA_PATH}/{bom_id}")))
@@ -401,132 +683,112 @@ impl BOM {
 
         satellite_images.sort();
 
+        let time_zone = self.satellite_time_zone(bom_id).await?;
+        let selected = options.select(&satellite_images);
         let mut images = Vec::new();
-        for file in satellite_images.iter().rev().take(30).rev() {
-            let img = self
-                .get_or_fetch_compressed_resized(file, "image/jpg", &mut ftp_client)
+        for (i, file) in selected.iter().enumerate() {
+            let mut img = self
+                .get_or_fetch_compressed_resized(file, "image/jpg")
                 .await?;
 
+            let label = frame_label(satellite_frame_time(file), time_zone);
+            overlay::stamp_frame(&mut img, &label, i, selected.len());
             images.push(img);
         }
 
-        tracing::info!("generating frames for satellite");
-        let frames = images.into_iter().map(|i| {
-            image::Frame::from_parts(i.to_rgba8(), 0, 0, Delay::from_numer_denom_ms(215, 1))
-        });
+        tracing::info!("encoding {} for satellite", format.name());
+        let encoded = Self::encode(images, options, format).await?;
 
-        tracing::info!("encoding gif for satellite");
-        let rt = tokio::runtime::Handle::current();
-
-        let final_gif = rt
-            .spawn_blocking(move || {
-                let mut final_gif = Vec::<u8>::new();
-                let mut final_gif_cursor = std::io::Cursor::new(&mut final_gif);
-                let mut gif_encoder = GifEncoder::new_with_speed(&mut final_gif_cursor, 1);
-                gif_encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
-                gif_encoder.encode_frames(frames)?;
-
-                drop(gif_encoder);
-
-                Ok::<Vec<_>, anyhow::Error>(final_gif)
-            })
-            .await??;
-
-        tracing::info!("final gif size: {}", final_gif.len());
+        tracing::info!("final {} size: {}", format.name(), encoded.len());
 
         self.bucket
-            .put_object_with_content_type(&bucket_path, &final_gif, "image/gif")
+            .put(&bucket_path, &encoded, format.content_type())
             .await?;
 
-        Ok((format!("{IMAGE_HOST}/{bucket_path}"), final_gif))
+        Ok((format!("{IMAGE_HOST}/{bucket_path}"), encoded))
     }
 
     pub async fn generate_radar_timelapse_24hr_for(
         &self,
         bom_id: &str,
+        options: &RenderOptions,
+        format: OutputFormat,
     ) -> Result<(String, Vec<u8>), BOMError> {
-        let bucket_path = format!("external/{}.radar.24h.gif", bom_id);
+        let bucket_path = Self::timelapse_bucket_path(bom_id, options, format);
 
-        let bucket = self.bucket();
-        let mut radar_objects = bucket
-            .list(RADAR_CACHE_PATH.to_owned(), None)
-            .await?
-            .into_iter()
-            .flat_map(|i| i.contents)
-            .filter(|o| {
-                o.key
-                    .starts_with(&format!("{RADAR_CACHE_PATH}/{bom_id}.T."))
-            })
-            .map(|o| o.key)
-            .collect::<Vec<_>>();
+        // only default ranges of prioritised radars are fetched in the
+        // background, anything else picks up whatever the source still has
+        if let Err(e) = self.fetch_all_radar_images_for(bom_id).await {
+            tracing::error!("radar image fetch for {bom_id} failed: {e}");
+        }
+
+        let mut radar_objects = self
+            .bucket
+            .list(&format!("{RADAR_CACHE_PATH}/{bom_id}.T."))
+            .await?;
 
         radar_objects.sort();
 
-        let base_image = self.bucket.get_object(format!("{bom_id}.base.png")).await?;
-        let base_image_bytes = base_image.into_bytes().to_vec();
+        let base_image_bytes = self.bucket.get(&format!("{bom_id}.base.png")).await?;
         let base_image = image::ImageReader::new(std::io::Cursor::new(base_image_bytes))
             .with_guessed_format()?
             .decode()?;
 
+        let time_zone = self.radar_time_zone(bom_id).await?;
+        let selected = options.select(&radar_objects);
         let mut images = Vec::new();
-        for file in radar_objects.iter() {
+        for (i, file) in selected.iter().enumerate() {
             let mut base_image_clone = base_image.clone();
 
             let img = self.get_image(RADAR_CACHE_PATH, file).await?;
 
             imageops::overlay(&mut base_image_clone, &img, 0, 0);
+            let label = frame_label(radar_frame_time(file), time_zone);
+            overlay::stamp_frame(&mut base_image_clone, &label, i, selected.len());
             images.push(base_image_clone);
         }
 
-        tracing::info!("generating gif for timelapse: {bom_id}");
-        let frames = images.into_iter().map(|i| {
-            image::Frame::from_parts(i.to_rgba8(), 0, 0, Delay::from_numer_denom_ms(10, 1))
-        });
-
-        let rt = tokio::runtime::Handle::current();
-
-        let final_gif = rt
-            .spawn_blocking(move || {
-                let mut final_gif = Vec::<u8>::new();
-                let mut final_gif_cursor = std::io::Cursor::new(&mut final_gif);
-                let mut gif_encoder = GifEncoder::new_with_speed(&mut final_gif_cursor, 1);
-                gif_encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
-                gif_encoder.encode_frames(frames)?;
-
-                drop(gif_encoder);
+        tracing::info!("generating {} for timelapse: {bom_id}", format.name());
+        let encoded = Self::encode(images, options, format).await?;
 
-                Ok::<Vec<_>, anyhow::Error>(final_gif)
-            })
-            .await??;
-
-        tracing::info!("final gif size: {}", final_gif.len());
+        tracing::info!("final {} size: {}", format.name(), encoded.len());
 
         self.bucket
-            .put_object_with_content_type(&bucket_path, &final_gif, "image/gif")
+            .put(&bucket_path, &encoded, format.content_type())
             .await?;
 
-        Ok((format!("{IMAGE_HOST}/{bucket_path}"), final_gif))
+        Ok((format!("{IMAGE_HOST}/{bucket_path}"), encoded))
     }
 
+    /// With `nowcast` predicted frames for the next hour are added after the
+    /// observed ones, if there's enough rain around to track.
     pub async fn generate_radar_gif_for(
         &self,
         bom_id: &str,
+        options: &RenderOptions,
+        format: OutputFormat,
+        nowcast: bool,
     ) -> Result<(String, Vec<u8>), BOMError> {
         let now = chrono::offset::Utc::now().naive_utc();
         let datetime = now.format("%Y%m%d%H%M").to_string();
-        let bucket_path = format!("external/{}.{datetime}.radar.gif", bom_id);
-
-        let existing_obj = self.bucket.head_object(&bucket_path).await;
-        if existing_obj.is_ok() {
+        let bucket_path = format!(
+            "external/{}.{datetime}.{}{}.radar.{}",
+            bom_id,
+            options.cache_key(),
+            if nowcast { ".nowcast" } else { "" },
+            format.extension()
+        );
+
+        if self.bucket.exists(&bucket_path).await? {
             return Ok((
                 format!("{IMAGE_HOST}/{bucket_path}"),
-                self.bucket().get_object(&bucket_path).await?.to_vec(),
+                self.bucket.get(&bucket_path).await?,
             ));
         }
 
-        let mut ftp_client = Self::get_ftp_client_session().await?;
-        let mut radar_images = ftp_client
-            .nlst(Some(RADAR_DATA_PATH))
+        let mut radar_images = self
+            .source
+            .list(RADAR_DATA_PATH)
             .await?
             .into_iter()
             .filter(|i| i.starts_with(&format!("{RADAR_DATA_PATH}/{bom_id}")))
@@ -535,48 +797,548 @@ impl BOM {
 
         radar_images.sort();
 
-        let base_image = self.bucket.get_object(format!("{bom_id}.base.png")).await?;
-        let base_image_bytes = base_image.into_bytes().to_vec();
+        let base_image_bytes = self.bucket.get(&format!("{bom_id}.base.png")).await?;
         let base_image = image::ImageReader::new(std::io::Cursor::new(base_image_bytes))
             .with_guessed_format()?
             .decode()?;
 
-        let mut images = Vec::new();
-        for file in radar_images.iter().rev().take(7).rev() {
+        let time_zone = self.radar_time_zone(bom_id).await?;
+        let mut frames = Vec::new();
+        for file in options.select(&radar_images) {
             let mut base_image_clone = base_image.clone();
 
             let img = self
-                .get_or_fetch_image(RADAR_CACHE_PATH, file, "image/png", &mut ftp_client)
+                .get_or_fetch_image(RADAR_CACHE_PATH, file, "image/png")
                 .await?;
 
             imageops::overlay(&mut base_image_clone, &img, 0, 0);
+            frames.push((
+                base_image_clone,
+                frame_label(radar_frame_time(file), time_zone),
+            ));
+        }
+
+        if nowcast {
+            let motion = self.motion_for(bom_id, &radar_images).await?;
+            let latest = radar_images
+                .last()
+                .and_then(|file| Some((file, radar_frame_time(file)?)));
+            if let (Some(motion), Some((latest, latest_time))) = (motion, latest) {
+                let latest = self
+                    .get_or_fetch_image(RADAR_CACHE_PATH, latest, "image/png")
+                    .await?;
+                for minutes in nowcast::FORECAST_STEPS {
+                    let mut base_image_clone = base_image.clone();
+                    let predicted = nowcast::advect(&latest, motion, minutes);
+
+                    imageops::overlay(&mut base_image_clone, &predicted, 0, 0);
+                    let time = latest_time + TimeDelta::minutes(minutes as i64);
+                    let label = format!("{} (NOWCAST)", frame_label(Some(time), time_zone));
+                    frames.push((base_image_clone, label));
+                }
+            }
+        }
+
+        let total = frames.len();
+        let images = frames
+            .into_iter()
+            .enumerate()
+            .map(|(i, (mut img, label))| {
+                overlay::stamp_frame(&mut img, &label, i, total);
+                img
+            })
+            .collect();
+
+        let encoded = Self::encode(images, options, format).await?;
+
+        self.bucket
+            .put(&bucket_path, &encoded, format.content_type())
+            .await?;
+
+        Ok((format!("{IMAGE_HOST}/{bucket_path}"), encoded))
+    }
+
+    /// Same as [`BOM::generate_radar_gif_for`] for a mosaic, without nowcasts
+    /// as the motion estimate is tuned for a single site.
+    pub async fn generate_mosaic_gif_for(
+        &self,
+        mosaic: &Mosaic,
+        options: &RenderOptions,
+        format: OutputFormat,
+    ) -> Result<(String, Vec<u8>), BOMError> {
+        let now = chrono::offset::Utc::now().naive_utc();
+        let datetime = now.format("%Y%m%d%H%M").to_string();
+        let bucket_path = format!(
+            "external/{}.{datetime}.{}.mosaic.{}",
+            mosaic.region,
+            options.cache_key(),
+            format.extension()
+        );
+
+        if self.bucket.exists(&bucket_path).await? {
+            return Ok((
+                format!("{IMAGE_HOST}/{bucket_path}"),
+                self.bucket.get(&bucket_path).await?,
+            ));
+        }
+
+        let frames = match &mosaic.source {
+            MosaicSource::Product {
+                bom_radar_id, crop, ..
+            } => self.mosaic_frames(bom_radar_id, *crop, options).await?,
+            MosaicSource::Stitched { sites } => {
+                self.stitched_frames(&mosaic.region, sites, options).await?
+            }
+        };
+
+        self.ensure_mosaic_background(mosaic).await?;
+        let base_image_bytes = self.bucket.get(&mosaic.background_path()).await?;
+        let base_image = image::ImageReader::new(std::io::Cursor::new(base_image_bytes))
+            .with_guessed_format()?
+            .decode()?;
+
+        let time_zone = mosaic.time_zone.parse().unwrap_or(Tz::UTC);
+        let total = frames.len();
+        let mut images = Vec::with_capacity(total);
+        for (i, (time, img)) in frames.into_iter().enumerate() {
+            let mut base_image_clone = base_image.clone();
+            imageops::overlay(&mut base_image_clone, &img, 0, 0);
+            let label = frame_label(time, time_zone);
+            overlay::stamp_frame(&mut base_image_clone, &label, i, total);
             images.push(base_image_clone);
         }
 
-        let frames = images.into_iter().map(|i| {
-            image::Frame::from_parts(i.to_rgba8(), 0, 0, Delay::from_numer_denom_ms(350, 1))
-        });
+        let encoded = Self::encode(images, options, format).await?;
+
+        self.bucket
+            .put(&bucket_path, &encoded, format.content_type())
+            .await?;
+
+        Ok((format!("{IMAGE_HOST}/{bucket_path}"), encoded))
+    }
+
+    /// Rain layers of a mosaic product, cropped to the region.
+    async fn mosaic_frames(
+        &self,
+        bom_id: &str,
+        crop: Option<Crop>,
+        options: &RenderOptions,
+    ) -> Result<Vec<(Option<DateTime<Utc>>, DynamicImage)>, BOMError> {
+        let mut radar_images = self
+            .source
+            .list(RADAR_DATA_PATH)
+            .await?
+            .into_iter()
+            .filter(|i| i.starts_with(&format!("{RADAR_DATA_PATH}/{bom_id}.")))
+            .filter(|i| i.ends_with(".png"))
+            .collect::<Vec<_>>();
+
+        radar_images.sort();
+
+        let mut frames = Vec::new();
+        for file in options.select(&radar_images) {
+            let mut img = self
+                .get_or_fetch_image(RADAR_CACHE_PATH, file, "image/png")
+                .await?;
+            if let Some(crop) = crop {
+                img = crop.apply(&img);
+            }
+
+            frames.push((radar_frame_time(file), img));
+        }
+
+        Ok(frames)
+    }
+
+    /// Rain layers stitched from each site, the site with the most recent
+    /// frames picks the frame times and the others use their frame closest
+    /// to each, so a site that's down doesn't hold up the rest.
+    async fn stitched_frames(
+        &self,
+        region: &str,
+        ids: &[String],
+        options: &RenderOptions,
+    ) -> Result<Vec<(Option<DateTime<Utc>>, DynamicImage)>, BOMError> {
+        let (sites, composite) = self.composite_for(region, ids).await?;
+
+        let listing = self.source.list(RADAR_DATA_PATH).await?;
+        let site_frames = sites
+            .iter()
+            .map(|site| {
+                let prefix = format!("{RADAR_DATA_PATH}/{}.", site.bom_radar_id);
+                let mut frames = listing
+                    .iter()
+                    .filter(|i| i.starts_with(&prefix) && i.ends_with(".png"))
+                    .filter_map(|i| Some((radar_frame_time(i)?, i.clone())))
+                    .collect::<Vec<_>>();
+                frames.sort();
+                frames
+            })
+            .collect::<Vec<_>>();
+
+        let times = site_frames
+            .iter()
+            .max_by_key(|frames| (frames.last().map(|(time, _)| *time), frames.len()))
+            .map(|frames| frames.iter().map(|(time, _)| *time).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let rt = tokio::runtime::Handle::current();
+        let mut frames = Vec::new();
+        for time in options.select(&times) {
+            let mut layers = Vec::with_capacity(site_frames.len());
+            for frames_for_site in &site_frames {
+                let closest = frames_for_site
+                    .iter()
+                    .min_by_key(|(frame_time, _)| (*frame_time - *time).abs())
+                    .filter(|(frame_time, _)| {
+                        (*frame_time - *time).abs() <= TimeDelta::minutes(STITCH_WINDOW_MINUTES)
+                    });
+
+                let layer = match closest {
+                    Some((_, file)) => Some(
+                        self.get_or_fetch_image(RADAR_CACHE_PATH, file, "image/png")
+                            .await?,
+                    ),
+                    None => None,
+                };
+                layers.push(layer);
+            }
+
+            let composite = composite.clone();
+            let img = rt
+                .spawn_blocking(move || {
+                    composite.stitch_frame(&layers.iter().map(Option::as_ref).collect::<Vec<_>>())
+                })
+                .await?;
+            frames.push((Some(*time), img));
+        }
+
+        Ok(frames)
+    }
+
+    /// Loads the most recent radar layers for motion estimates, oldest first.
+    async fn load_radar_history(
+        &self,
+        radar_images: &[String],
+    ) -> Result<Vec<(DateTime<Utc>, DynamicImage)>, BOMError> {
+        let start = radar_images.len().saturating_sub(nowcast::HISTORY_FRAMES);
+        let mut history = Vec::with_capacity(nowcast::HISTORY_FRAMES);
+        for file in &radar_images[start..] {
+            let Some(time) = radar_frame_time(file) else {
+                continue;
+            };
+
+            let img = self
+                .get_or_fetch_image(RADAR_CACHE_PATH, file, "image/png")
+                .await?;
+            history.push((time, img));
+        }
+
+        Ok(history)
+    }
 
+    async fn estimate_motion(
+        history: Vec<(DateTime<Utc>, DynamicImage)>,
+    ) -> Result<Option<Motion>, BOMError> {
         let rt = tokio::runtime::Handle::current();
+        let motion = rt
+            .spawn_blocking(move || nowcast::estimate_motion(&nowcast::decode_frames(&history)))
+            .await?;
 
-        let final_gif = rt
-            .spawn_blocking(move || {
-                let mut final_gif = Vec::<u8>::new();
-                let mut final_gif_cursor = std::io::Cursor::new(&mut final_gif);
-                let mut gif_encoder = GifEncoder::new_with_speed(&mut final_gif_cursor, 1);
-                gif_encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
-                gif_encoder.encode_frames(frames)?;
+        Ok(motion)
+    }
 
-                drop(gif_encoder);
+    /// Echo motion up to the latest of a radar's frames (sorted), only
+    /// estimated the first time it's asked for after a new frame arrives.
+    async fn motion_for(
+        &self,
+        bom_id: &str,
+        frames: &[String],
+    ) -> Result<Option<Motion>, BOMError> {
+        let Some(latest) = frames.last().and_then(|f| radar_frame_time(f)) else {
+            return Ok(None);
+        };
+
+        let cell = {
+            let mut motions = self.motions.lock().unwrap();
+            match motions.get(bom_id) {
+                Some((time, cell)) if *time == latest => cell.clone(),
+                _ => {
+                    let cell = Arc::new(OnceCell::new());
+                    motions.insert(bom_id.to_owned(), (latest, cell.clone()));
+                    cell
+                }
+            }
+        };
 
-                Ok::<Vec<_>, anyhow::Error>(final_gif)
+        let motion = cell
+            .get_or_try_init(|| async {
+                let history = self.load_radar_history(frames).await?;
+                Self::estimate_motion(history).await
             })
-            .await??;
+            .await?;
 
-        self.bucket
-            .put_object_with_content_type(&bucket_path, &final_gif, "image/gif")
+        Ok(*motion)
+    }
+
+    /// Estimates the motion for a radar's newest frames ahead of time, called
+    /// from the background refresh so requests don't have to.
+    pub async fn refresh_motion_for(&self, bom_id: &str) -> Result<(), BOMError> {
+        let frames = self.list_radar_frames(bom_id).await?;
+        self.motion_for(bom_id, &frames).await?;
+
+        Ok(())
+    }
+
+    /// Radar frames currently available from the source, oldest first.
+    async fn list_radar_frames(&self, bom_id: &str) -> Result<Vec<String>, BOMError> {
+        let mut frames = self
+            .source
+            .list(RADAR_DATA_PATH)
+            .await?
+            .into_iter()
+            .filter(|i| i.starts_with(&format!("{RADAR_DATA_PATH}/{bom_id}.T.")))
+            .filter(|i| i.ends_with(".png"))
+            .collect::<Vec<_>>();
+
+        frames.sort();
+        Ok(frames)
+    }
+
+    /// Decodes the most recent radar frame into rain rates, returning when it was captured.
+    pub async fn get_latest_rainfall_for(
+        &self,
+        bom_id: &str,
+    ) -> Result<(DateTime<Utc>, RainfallGrid), BOMError> {
+        let latest = self
+            .list_radar_frames(bom_id)
+            .await?
+            .pop()
+            .ok_or_else(|| BOMError::NoRadarFrames(bom_id.to_owned()))?;
+
+        let time =
+            radar_frame_time(&latest).ok_or_else(|| BOMError::NoRadarFrames(bom_id.to_owned()))?;
+        let img = self
+            .get_or_fetch_image(RADAR_CACHE_PATH, &latest, "image/png")
             .await?;
 
-        Ok((format!("{IMAGE_HOST}/{bucket_path}"), final_gif))
+        Ok((time, RainfallGrid::decode(&img)))
+    }
+
+    async fn radar_sites(&self) -> Result<Vec<RadarSite>, BOMError> {
+        let sites = sqlx::query!(
+            "SELECT name, bom_radar_id, latitude, longitude, ranges FROM locations WHERE active"
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .filter_map(|row| {
+            Some(RadarSite {
+                name: row.name,
+                bom_radar_id: row.bom_radar_id,
+                latitude: row.latitude?,
+                longitude: row.longitude?,
+                ranges: row.ranges,
+            })
+        })
+        .collect();
+
+        Ok(sites)
+    }
+
+    pub async fn get_radar_site(&self, bom_id: &str) -> Result<RadarSite, BOMError> {
+        self.radar_sites()
+            .await?
+            .into_iter()
+            .find(|site| site.bom_radar_id == bom_id)
+            .ok_or_else(|| BOMError::UnknownRadar(bom_id.to_owned()))
+    }
+
+    pub async fn get_nearest_radar_site(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<RadarSite, BOMError> {
+        radar::nearest_site(self.radar_sites().await?, latitude, longitude).ok_or(
+            BOMError::OutOfRange {
+                latitude,
+                longitude,
+            },
+        )
+    }
+
+    /// The radar product that best shows a point, generating its background
+    /// if it's a range that isn't registered.
+    pub async fn get_best_radar_for(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<RadarSite, BOMError> {
+        let site = radar::best_product(self.radar_sites().await?, latitude, longitude).ok_or(
+            BOMError::OutOfRange {
+                latitude,
+                longitude,
+            },
+        )?;
+
+        self.ensure_radar_background(&site.bom_radar_id).await?;
+
+        Ok(site)
+    }
+
+    /// The same site's product at another range, if the site publishes it.
+    pub async fn get_radar_product_for_range(
+        &self,
+        bom_id: &str,
+        range_km: i32,
+    ) -> Result<String, BOMError> {
+        let ranges = sqlx::query_scalar!(
+            "SELECT ranges FROM locations WHERE left(bom_radar_id, 5) = left($1, 5) LIMIT 1",
+            bom_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| BOMError::UnknownRadar(bom_id.to_owned()))?;
+
+        let product = radar::product_for_range(bom_id, range_km)
+            .filter(|_| ranges.contains(&range_km))
+            .ok_or_else(|| BOMError::UnsupportedRange {
+                radar: bom_id.to_owned(),
+                range_km,
+            })?;
+        self.ensure_radar_background(&product).await?;
+
+        Ok(product)
+    }
+
+    /// Reads the rain rate at a point from the last hour of frames of the
+    /// nearest radar that covers it.
+    pub async fn get_rain_at(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<PointRainfall, BOMError> {
+        let site = self.get_nearest_radar_site(latitude, longitude).await?;
+        let (x, y) = site
+            .pixel_for(latitude, longitude)
+            .ok_or(BOMError::OutOfRange {
+                latitude,
+                longitude,
+            })?;
+
+        let frames = self.list_radar_frames(&site.bom_radar_id).await?;
+        let latest = frames
+            .last()
+            .and_then(|f| radar_frame_time(f))
+            .ok_or_else(|| BOMError::NoRadarFrames(site.bom_radar_id.clone()))?;
+        let since = latest - TimeDelta::hours(1);
+
+        let mut samples = Vec::new();
+        for file in &frames {
+            let Some(time) = radar_frame_time(file).filter(|time| *time >= since) else {
+                continue;
+            };
+
+            let img = self
+                .get_or_fetch_image(RADAR_CACHE_PATH, file, "image/png")
+                .await?;
+            let rate = if img.in_bounds(x, y) {
+                rainfall::rate_for(img.get_pixel(x, y))
+            } else {
+                0.0
+            };
+
+            samples.push((time, rate));
+        }
+
+        let motion = self.motion_for(&site.bom_radar_id, &frames).await?;
+        let expected_in_minutes = match (motion, frames.last()) {
+            (Some(motion), Some(latest)) => {
+                let latest = self
+                    .get_or_fetch_image(RADAR_CACHE_PATH, latest, "image/png")
+                    .await?;
+                nowcast::minutes_until_rain(&latest, motion, x, y, nowcast::HORIZON_MINUTES)
+            }
+            _ => None,
+        };
+
+        Ok(PointRainfall {
+            site,
+            samples,
+            expected_in_minutes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{source::LocalDirImageSource, storage::MemoryObjectStore};
+    use image::{codecs::gif::GifDecoder, AnimationDecoder, Frame};
+    use pretty_assertions::assert_eq;
+
+    async fn fixture_bom(db: PgPool) -> BOM {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ftp");
+        BOM::new(
+            Box::new(LocalDirImageSource::new(fixtures)),
+            Box::new(MemoryObjectStore::new()),
+            db,
+        )
+        .await
+        .unwrap()
+    }
+
+    fn gif_frames(gif: Vec<u8>) -> Vec<Frame> {
+        GifDecoder::new(std::io::Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn timelapse_from_fixture_frames(db: PgPool) {
+        let bom = fixture_bom(db).await;
+
+        bom.fetch_all_radar_images_for("IDR703").await.unwrap();
+        bom.generate_radar_background("IDR703").await.unwrap();
+        let (url, gif) = bom
+            .generate_radar_timelapse_24hr_for(
+                "IDR703",
+                &RenderOptions::TIMELAPSE,
+                OutputFormat::Gif,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(url, format!("{IMAGE_HOST}/external/IDR703.radar.24h.gif"));
+        assert!(bom
+            .bucket()
+            .exists("external/IDR703.radar.24h.gif")
+            .await
+            .unwrap());
+
+        let frames = gif_frames(gif);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].buffer().dimensions(), (16, 16));
+    }
+
+    #[sqlx::test]
+    async fn timelapse_at_another_range(db: PgPool) {
+        let bom = fixture_bom(db).await;
+
+        // nothing fetches the 256km frames in the background
+        let product = bom
+            .get_radar_product_for_range("IDR703", 256)
+            .await
+            .unwrap();
+        let (url, gif) = bom
+            .get_radar_timelapse_24hr_for(&product, &RenderOptions::TIMELAPSE, OutputFormat::Gif)
+            .await
+            .unwrap();
+
+        assert_eq!(product, "IDR702");
+        assert_eq!(url, format!("{IMAGE_HOST}/external/IDR702.radar.24h.gif"));
+        assert_eq!(gif_frames(gif).len(), 2);
     }
 }
@@ -0,0 +1,164 @@
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+use crate::{radar::RadarSite, rainfall};
+
+/// Longest side of a stitched frame, about a km a pixel for a few sites.
+const MAX_SIZE: u32 = 1024;
+
+const KM_PER_DEGREE: f64 = 111.32;
+
+/// Shown where none of the sites reach.
+const NO_COVERAGE: Rgba<u8> = Rgba([200, 200, 200, 255]);
+
+/// An equirectangular lat/lng grid that covers every site's frame.
+#[derive(Debug, Clone, Copy)]
+struct Grid {
+    north: f64,
+    west: f64,
+    degrees_per_pixel_lat: f64,
+    degrees_per_pixel_lng: f64,
+    width: u32,
+    height: u32,
+}
+
+impl Grid {
+    fn covering(sites: &[RadarSite]) -> Option<Self> {
+        if sites.is_empty() {
+            return None;
+        }
+
+        let (mut north, mut south) = (f64::MIN, f64::MAX);
+        let (mut west, mut east) = (f64::MAX, f64::MIN);
+        for site in sites {
+            let range_km = site.range_km()?;
+            let lat_span = range_km / KM_PER_DEGREE;
+            let lng_span = range_km / (KM_PER_DEGREE * site.latitude.to_radians().cos());
+
+            north = north.max(site.latitude + lat_span);
+            south = south.min(site.latitude - lat_span);
+            west = west.min(site.longitude - lng_span);
+            east = east.max(site.longitude + lng_span);
+        }
+
+        // keep km square in the middle of the grid
+        let middle = ((north + south) / 2.0).to_radians().cos();
+        let (height_km, width_km) = (north - south, (east - west) * middle);
+        let (width, height) = if width_km >= height_km {
+            (
+                MAX_SIZE,
+                (MAX_SIZE as f64 * height_km / width_km).ceil() as u32,
+            )
+        } else {
+            (
+                (MAX_SIZE as f64 * width_km / height_km).ceil() as u32,
+                MAX_SIZE,
+            )
+        };
+
+        Some(Self {
+            north,
+            west,
+            degrees_per_pixel_lat: (north - south) / height as f64,
+            degrees_per_pixel_lng: (east - west) / width as f64,
+            width,
+            height,
+        })
+    }
+
+    /// Latitude and longitude of the centre of a pixel.
+    fn location(&self, x: u32, y: u32) -> (f64, f64) {
+        (
+            self.north - (y as f64 + 0.5) * self.degrees_per_pixel_lat,
+            self.west + (x as f64 + 0.5) * self.degrees_per_pixel_lng,
+        )
+    }
+}
+
+/// Reprojects frames from neighbouring sites onto one grid. Where sites
+/// overlap the rain comes from whichever sees it heaviest and the background
+/// from the closest site.
+pub struct Composite {
+    grid: Grid,
+    /// For every grid pixel, the sites that see it closest first with the
+    /// pixel to read in that site's frame.
+    lookups: Vec<Vec<(usize, u32, u32)>>,
+}
+
+impl Composite {
+    pub fn new(sites: &[RadarSite]) -> Option<Self> {
+        let grid = Grid::covering(sites)?;
+
+        let mut lookups = Vec::with_capacity((grid.width * grid.height) as usize);
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                let (latitude, longitude) = grid.location(x, y);
+                let mut seen_by = sites
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, site)| {
+                        let (px, py) = site.pixel_for(latitude, longitude)?;
+                        Some((site.distance_km(latitude, longitude), (i, px, py)))
+                    })
+                    .collect::<Vec<_>>();
+
+                seen_by.sort_by(|a, b| a.0.total_cmp(&b.0));
+                lookups.push(seen_by.into_iter().map(|(_, lookup)| lookup).collect());
+            }
+        }
+
+        Some(Self { grid, lookups })
+    }
+
+    fn pixels(&self) -> impl Iterator<Item = (u32, u32, &Vec<(usize, u32, u32)>)> {
+        let width = self.grid.width;
+        self.lookups
+            .iter()
+            .enumerate()
+            .map(move |(i, lookup)| (i as u32 % width, i as u32 / width, lookup))
+    }
+
+    /// Stitches each site's background (`None` if it couldn't be made) with
+    /// the closest site winning, the legend below the map is left out.
+    pub fn stitch_background(&self, backgrounds: &[Option<DynamicImage>]) -> DynamicImage {
+        let mut img = RgbaImage::from_pixel(self.grid.width, self.grid.height, NO_COVERAGE);
+
+        for (x, y, lookup) in self.pixels() {
+            let pixel = lookup.iter().find_map(|(site, px, py)| {
+                let background = backgrounds.get(*site)?.as_ref()?;
+                let in_frame = *px < background.width() && *py < background.height();
+                in_frame.then(|| background.get_pixel(*px, *py))
+            });
+
+            if let Some(pixel) = pixel {
+                img.put_pixel(x, y, pixel);
+            }
+        }
+
+        DynamicImage::ImageRgba8(img)
+    }
+
+    /// Stitches one frame from each site (`None` if a site has no frame near
+    /// the time), keeping the heaviest rain where they overlap.
+    pub fn stitch_frame(&self, frames: &[Option<&DynamicImage>]) -> DynamicImage {
+        let mut img = RgbaImage::new(self.grid.width, self.grid.height);
+
+        for (x, y, lookup) in self.pixels() {
+            let heaviest = lookup
+                .iter()
+                .filter_map(|(site, px, py)| {
+                    let frame = frames.get(*site).copied().flatten()?;
+                    let in_frame = *px < frame.width() && *py < frame.height();
+                    in_frame.then(|| frame.get_pixel(*px, *py))
+                })
+                .map(|pixel| (rainfall::rate_for(pixel), pixel))
+                .filter(|(rate, _)| *rate > 0.0)
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+
+            if let Some((_, pixel)) = heaviest {
+                img.put_pixel(x, y, pixel);
+            }
+        }
+
+        DynamicImage::ImageRgba8(img)
+    }
+}
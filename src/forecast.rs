@@ -0,0 +1,545 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use phf::phf_map;
+use sqlx::PgPool;
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+
+use crate::{
+    bom::BOM,
+    openmeteo::{OpenMeteoAPI, OpenMeteoAPIError},
+    precis::{PrecisError, PrecisForecasts},
+    types::{ForecastForDay, ForecastForHour, Location},
+    willyweather::{WillyWeatherAPI, WillyWeatherAPIError},
+};
+
+pub const PRECIS_TO_EMOJI: phf::Map<&'static str, &'static str> = phf_map! {
+    "fine" => "☀️",
+    "mostly-fine" => "🌤️",
+    "high-cloud" => "☁️",
+    "partly-cloudy" => "⛅",
+    "mostly-cloudy" => "🌥️",
+    "cloudy" => "☁️",
+    "overcast" => "🌫️",
+    "shower-or-two" => "🌦️",
+    "chance-shower-fine" => "🌧️",
+    "chance-shower-cloud" => "🌧️",
+    "drizzle" => "🌧️",
+    "few-showers" => "🌦️",
+    "showers-rain" => "🌧️",
+    "heavy-showers-rain" => "🌧️",
+    "chance-thunderstorm-fine" => "⛈️",
+    "chance-thunderstorm-cloud" => "⛈️",
+    "chance-thunderstorm-showers" => "⛈️",
+    "thunderstorm" => "⛈️",
+    "chance-snow-fine" => "🌨️",
+    "chance-snow-cloud" => "🌨️",
+    "snow-and-rain" => "🌨️",
+    "light-snow" => "🌨️",
+    "snow" => "❄️",
+    "heavy-snow" => "🌨️",
+    "wind" => "💨",
+    "frost" => "🧊",
+    "fog" => "🌁",
+    "hail" => "🌨️",
+    "dust" => "🌪️",
+};
+
+/// Hourly forecasts cover the next day by default and at most two days.
+pub const DEFAULT_HOURS: i64 = 24;
+pub const MAX_HOURS: i64 = 48;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ForecastError {
+    #[error("a willyweather error occurred: {0}")]
+    WillyWeather(#[from] WillyWeatherAPIError),
+
+    #[error("a open-meteo error occurred: {0}")]
+    OpenMeteo(#[from] OpenMeteoAPIError),
+
+    #[error("a precis error occurred: {0}")]
+    Precis(#[from] PrecisError),
+
+    #[error("a sql error occurred: {0}")]
+    Sql(#[from] sqlx::Error),
+
+    #[error("a date parsing error occurred: {0}")]
+    Date(#[from] chrono::ParseError),
+
+    #[error("unknown forecast location: {0}")]
+    UnknownLocation(String),
+
+    #[error("unknown time zone: {0}")]
+    UnknownTimeZone(String),
+
+    #[error("{0} doesn't exist in the location's time zone")]
+    InvalidLocalTime(NaiveDateTime),
+
+    #[error("{0} doesn't have hourly forecasts")]
+    HourlyUnsupported(&'static str),
+
+    #[error("{location} isn't set up for {provider} forecasts")]
+    NotConfigured {
+        location: String,
+        provider: &'static str,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    WillyWeather,
+    OpenMeteo,
+    Bom,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 3] = [Self::WillyWeather, Self::OpenMeteo, Self::Bom];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::WillyWeather => "willyweather",
+            Self::OpenMeteo => "open-meteo",
+            Self::Bom => "bom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    pub fn footer(&self) -> &'static str {
+        match self {
+            Self::WillyWeather => "BOM charges $4,037.00 for this data",
+            Self::OpenMeteo => "Weather data by Open-Meteo.com",
+            Self::Bom => "From the BOM precis forecast",
+        }
+    }
+}
+
+/// A row of `forecast_locations`, each provider uses the columns it needs.
+#[derive(Debug, Clone)]
+pub struct ForecastLocation {
+    pub id: String,
+    pub name: String,
+    pub provider: Provider,
+    pub willyweather_id: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub precis_product: Option<String>,
+    pub precis_area: Option<String>,
+}
+
+impl ForecastLocation {
+    pub async fn get(db: &PgPool, id: &str) -> Result<Self, ForecastError> {
+        let row = sqlx::query!("SELECT * FROM forecast_locations WHERE id = ($1)", id)
+            .fetch_optional(db)
+            .await?
+            .ok_or_else(|| ForecastError::UnknownLocation(id.to_owned()))?;
+
+        Ok(Self {
+            provider: Provider::from_name(&row.provider).unwrap_or(Provider::WillyWeather),
+            id: row.id,
+            name: row.name,
+            willyweather_id: row.willyweather_id,
+            latitude: row.latitude,
+            longitude: row.longitude,
+            precis_product: row.precis_product,
+            precis_area: row.precis_area,
+        })
+    }
+
+    /// Locations whose name contains `query`, for autocomplete.
+    pub async fn search(db: &PgPool, query: &str) -> Result<Vec<(String, String)>, ForecastError> {
+        let locations = sqlx::query!(
+            r#"SELECT id, name FROM forecast_locations
+                WHERE name ILIKE '%' || $1 || '%'
+                ORDER BY name
+                LIMIT 25"#,
+            query
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|row| (row.id, row.name))
+        .collect();
+
+        Ok(locations)
+    }
+
+    /// Stores a WillyWeather location, returns false if it was already added.
+    pub async fn add(
+        db: &PgPool,
+        location: &Location,
+        provider: Provider,
+    ) -> Result<bool, ForecastError> {
+        let id = location.id.to_string();
+        let inserted = sqlx::query!(
+            r#"INSERT INTO forecast_locations (id, name, provider, willyweather_id, latitude, longitude)
+                VALUES ($1, $2, $3, $1, $4, $5)
+                ON CONFLICT (id) DO NOTHING"#,
+            id,
+            location.name,
+            provider.name(),
+            location.lat,
+            location.lng
+        )
+        .execute(db)
+        .await?;
+
+        Ok(inserted.rows_affected() > 0)
+    }
+
+    pub fn not_configured(&self, provider: Provider) -> ForecastError {
+        ForecastError::NotConfigured {
+            location: self.name.clone(),
+            provider: provider.name(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ForecastDay {
+    pub date_time: DateTime<FixedOffset>,
+    pub precis_code: String,
+    pub description: String,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    pub uv: Option<f64>,
+    /// Chance of any rain in percent.
+    pub chance_of_rain: Option<i64>,
+    pub sunrise: Option<DateTime<FixedOffset>>,
+    pub sunset: Option<DateTime<FixedOffset>>,
+}
+
+impl ForecastDay {
+    pub fn emoji(&self) -> &'static str {
+        PRECIS_TO_EMOJI.get(&self.precis_code).map_or("", |e| e)
+    }
+}
+
+/// A provider neutral daily forecast.
+#[derive(Debug, Clone)]
+pub struct DailyForecast {
+    pub location: String,
+    pub provider: Provider,
+    pub days: Vec<ForecastDay>,
+}
+
+impl DailyForecast {
+    pub fn endpoint_days(&self) -> Vec<ForecastForDay> {
+        self.days
+            .iter()
+            .map(|day| ForecastForDay {
+                date_time: day.date_time.to_rfc3339(),
+                code: day.precis_code.clone(),
+                description: day.description.clone(),
+                emoji: day.emoji().to_owned(),
+                min: day.min,
+                max: day.max,
+                uv: day.uv,
+                chance_of_rain: day.chance_of_rain,
+                sunrise: day.sunrise.map(|time| time.to_rfc3339()),
+                sunset: day.sunset.map(|time| time.to_rfc3339()),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ForecastHour {
+    pub date_time: DateTime<FixedOffset>,
+    pub temperature: Option<f64>,
+    /// Chance of any rain in percent.
+    pub chance_of_rain: Option<i64>,
+    pub wind_speed: Option<f64>,
+    pub wind_direction: Option<String>,
+}
+
+/// A provider neutral hourly forecast.
+#[derive(Debug, Clone)]
+pub struct HourlyForecast {
+    pub location: String,
+    pub provider: Provider,
+    pub hours: Vec<ForecastHour>,
+}
+
+impl HourlyForecast {
+    pub fn endpoint_hours(&self) -> Vec<ForecastForHour> {
+        self.hours
+            .iter()
+            .map(|hour| ForecastForHour {
+                date_time: hour.date_time.to_rfc3339(),
+                temperature: hour.temperature,
+                chance_of_rain: hour.chance_of_rain,
+                wind_speed: hour.wind_speed,
+                wind_direction: hour.wind_direction.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Providers send local times without an offset, this attaches the offset in
+/// effect at that time in the location's time zone.
+pub fn localise(
+    time: NaiveDateTime,
+    time_zone: Tz,
+) -> Result<DateTime<FixedOffset>, ForecastError> {
+    time_zone
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.fixed_offset())
+        .ok_or(ForecastError::InvalidLocalTime(time))
+}
+
+pub fn parse_time_zone(time_zone: &str) -> Result<Tz, ForecastError> {
+    time_zone
+        .parse()
+        .map_err(|_| ForecastError::UnknownTimeZone(time_zone.to_owned()))
+}
+
+/// "Today" is the current date where the forecast is for, not in utc.
+pub fn format_day(date_time: &DateTime<FixedOffset>) -> String {
+    let today: NaiveDate = Utc::now().with_timezone(date_time.offset()).date_naive();
+    if date_time.date_naive() == today {
+        "Today".to_owned()
+    } else {
+        date_time.format("%A %d/%m").to_string()
+    }
+}
+
+/// 16 point compass direction for a bearing in degrees.
+pub fn compass_direction(degrees: f64) -> &'static str {
+    const DIRECTIONS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+
+    let index = (degrees.rem_euclid(360.0) / 22.5).round() as usize % DIRECTIONS.len();
+    DIRECTIONS[index]
+}
+
+#[async_trait]
+pub trait ForecastProvider: Send + Sync {
+    async fn get_forecast(
+        &self,
+        location: &ForecastLocation,
+        days: i64,
+    ) -> Result<DailyForecast, ForecastError>;
+
+    /// The next `hours` hours starting from the current hour.
+    async fn get_hourly_forecast(
+        &self,
+        location: &ForecastLocation,
+        hours: i64,
+    ) -> Result<HourlyForecast, ForecastError>;
+}
+
+/// Picks the provider for each location, the BOM precis forecast is free so
+/// it's used when the location's provider isn't configured or fails.
+#[derive(Clone)]
+pub struct Forecaster {
+    bom: Arc<BOM>,
+    willyweather: Option<WillyWeatherAPI>,
+    open_meteo: OpenMeteoAPI,
+    precis: PrecisForecasts,
+    /// Set from `FORECAST_PROVIDER`, overrides every location.
+    provider_override: Option<Provider>,
+}
+
+impl Forecaster {
+    pub fn new(
+        bom: Arc<BOM>,
+        willyweather: Option<WillyWeatherAPI>,
+        provider_override: Option<Provider>,
+    ) -> Self {
+        Self {
+            precis: PrecisForecasts::new(bom.clone()),
+            bom,
+            willyweather,
+            open_meteo: OpenMeteoAPI::new(),
+            provider_override,
+        }
+    }
+
+    pub fn willyweather(&self) -> Option<&WillyWeatherAPI> {
+        self.willyweather.as_ref()
+    }
+
+    pub fn db(&self) -> &PgPool {
+        self.bom.db()
+    }
+
+    fn provider(&self, provider: Provider) -> Option<&dyn ForecastProvider> {
+        match provider {
+            Provider::WillyWeather => self
+                .willyweather
+                .as_ref()
+                .map(|w| w as &dyn ForecastProvider),
+            Provider::OpenMeteo => Some(&self.open_meteo),
+            Provider::Bom => Some(&self.precis),
+        }
+    }
+
+    pub async fn get_forecast(
+        &self,
+        location: &str,
+        days: i64,
+    ) -> Result<DailyForecast, ForecastError> {
+        let location = ForecastLocation::get(self.bom.db(), location).await?;
+        let provider = self.provider_override.unwrap_or(location.provider);
+
+        if provider != Provider::Bom {
+            match self.provider(provider) {
+                Some(forecasts) => match forecasts.get_forecast(&location, days).await {
+                    Ok(forecast) => return Ok(forecast),
+                    Err(e) => tracing::error!(
+                        "{} failed for {}, falling back to bom: {e}",
+                        provider.name(),
+                        location.id
+                    ),
+                },
+                None => tracing::warn!("{} isn't configured, using bom", provider.name()),
+            }
+        }
+
+        self.precis.get_forecast(&location, days).await
+    }
+
+    /// Open-Meteo is free and has hourly data for anywhere, so it's used when
+    /// the location's provider doesn't have hourly forecasts or fails.
+    pub async fn get_hourly_forecast(
+        &self,
+        location: &str,
+        hours: i64,
+    ) -> Result<HourlyForecast, ForecastError> {
+        let location = ForecastLocation::get(self.bom.db(), location).await?;
+        let provider = self.provider_override.unwrap_or(location.provider);
+
+        if provider != Provider::OpenMeteo {
+            match self.provider(provider) {
+                Some(forecasts) => match forecasts.get_hourly_forecast(&location, hours).await {
+                    Ok(forecast) => return Ok(forecast),
+                    Err(e) => tracing::error!(
+                        "hourly {} failed for {}, falling back to open-meteo: {e}",
+                        provider.name(),
+                        location.id
+                    ),
+                },
+                None => tracing::warn!("{} isn't configured, using open-meteo", provider.name()),
+            }
+        }
+
+        self.open_meteo.get_hourly_forecast(&location, hours).await
+    }
+}
+
+/// The embed used by `/forecast` and scheduled forecast posts.
+pub fn forecast_embed(forecast: &DailyForecast) -> EmbedBuilder {
+    let mut embed = EmbedBuilder::new()
+        .title(format!("🌡️ Forecast for {}", forecast.location))
+        .color(0x003366)
+        .footer(EmbedFooterBuilder::new(forecast.provider.footer()));
+
+    for day in &forecast.days {
+        let formatted_date = format_day(&day.date_time);
+
+        let mut details = Vec::new();
+        if let Some(max) = day.max {
+            details.push(format!("**Max:** {}°c", max));
+        }
+        if let Some(min) = day.min {
+            details.push(format!("**Min:** {}°c", min));
+        }
+        if let Some(uv) = day.uv {
+            details.push(format!("**UV:** {:.1}", uv));
+        }
+        if let Some(chance_of_rain) = day.chance_of_rain {
+            details.push(format!("**Rain:** {}%", chance_of_rain));
+        }
+        if let (Some(sunrise), Some(sunset)) = (day.sunrise, day.sunset) {
+            details.push(format!(
+                "🌅 {} 🌇 {}",
+                sunrise.format("%H:%M"),
+                sunset.format("%H:%M")
+            ));
+        }
+
+        embed = embed.field(
+            EmbedFieldBuilder::new(
+                formatted_date,
+                format!(
+                    "{} — {} {}",
+                    details.join(", "),
+                    day.emoji(),
+                    day.description
+                ),
+            )
+            .build(),
+        )
+    }
+
+    embed
+}
+
+/// The embed used by `/forecast hourly`, a line per hour grouped by day.
+pub fn hourly_forecast_embed(forecast: &HourlyForecast) -> EmbedBuilder {
+    let mut description = String::new();
+    let mut current_day = None;
+
+    for hour in &forecast.hours {
+        let day = hour.date_time.date_naive();
+        if current_day != Some(day) {
+            current_day = Some(day);
+            description.push_str(&format!("**{}**\n", format_day(&hour.date_time)));
+        }
+
+        let mut details = Vec::new();
+        if let Some(temperature) = hour.temperature {
+            details.push(format!("🌡️ {temperature:.0}°c"));
+        }
+        if let Some(chance_of_rain) = hour.chance_of_rain {
+            details.push(format!("🌧️ {chance_of_rain}%"));
+        }
+        if let Some(wind_speed) = hour.wind_speed {
+            let direction = hour.wind_direction.as_deref().unwrap_or("");
+            details.push(format!("💨 {direction} {wind_speed:.0} km/h"));
+        }
+
+        description.push_str(&format!(
+            "`{}` {}\n",
+            hour.date_time.format("%H:%M"),
+            details.join(" · ")
+        ));
+    }
+
+    EmbedBuilder::new()
+        .title(format!("🕐 Hourly forecast for {}", forecast.location))
+        .description(description)
+        .color(0x003366)
+        .footer(EmbedFooterBuilder::new(forecast.provider.footer()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0.0, "N")]
+    #[case(11.0, "N")]
+    #[case(22.5, "NNE")]
+    #[case(90.0, "E")]
+    #[case(180.0, "S")]
+    #[case(247.5, "WSW")]
+    #[case(337.5, "NNW")]
+    #[case(355.0, "N")]
+    #[case(360.0, "N")]
+    #[case(-90.0, "W")]
+    #[case(765.0, "NE")]
+    fn compass(#[case] degrees: f64, #[case] expected: &str) {
+        assert_eq!(compass_direction(degrees), expected);
+    }
+}
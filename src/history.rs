@@ -0,0 +1,394 @@
+use std::io::Cursor;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use image::{ImageFormat, Rgba, RgbaImage};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::{
+    observations::{ObservationsAPI, ObservationsAPIError},
+    overlay,
+};
+
+const CHART_WIDTH: u32 = 800;
+const CHART_HEIGHT: u32 = 400;
+const MARGIN_LEFT: u32 = 64;
+const MARGIN_RIGHT: u32 = 24;
+const MARGIN_TOP: u32 = 40;
+const MARGIN_BOTTOM: u32 = 32;
+const Y_TICKS: u32 = 5;
+const X_TICKS: u32 = 4;
+
+/// Readings further apart than this are drawn as a gap, the station was down.
+const MAX_GAP_MINUTES: i64 = 180;
+
+/// Longest period that can be charted or exported.
+pub const MAX_PERIOD_DAYS: i64 = 90;
+
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const AXIS: Rgba<u8> = Rgba([64, 64, 64, 255]);
+const GRID: Rgba<u8> = Rgba([224, 224, 224, 255]);
+
+#[derive(thiserror::Error, Debug)]
+pub enum HistoryError {
+    #[error("a sql error occurred: {0}")]
+    Sql(#[from] sqlx::Error),
+
+    #[error("a observations error occurred: {0}")]
+    Observations(#[from] ObservationsAPIError),
+
+    #[error("a image error occurred: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    Temp,
+    Rain,
+    Wind,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 3] = [Self::Temp, Self::Rain, Self::Wind];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Temp => "temp",
+            Self::Rain => "rain",
+            Self::Wind => "wind",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Temp => "temperature",
+            Self::Rain => "rain since 9am",
+            Self::Wind => "wind speed",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            // the chart font has no degree sign
+            Self::Temp => "C",
+            Self::Rain => "mm",
+            Self::Wind => "km/h",
+        }
+    }
+
+    fn colour(&self) -> Rgba<u8> {
+        match self {
+            Self::Temp => Rgba([204, 51, 0, 255]),
+            Self::Rain => Rgba([0, 102, 204, 255]),
+            Self::Wind => Rgba([0, 136, 68, 255]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct HistoryPoint {
+    pub time: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// Parses periods like `24h`, `7d` or `2w`.
+pub fn parse_period(period: &str) -> Option<TimeDelta> {
+    let period = period.trim().to_lowercase();
+    let unit = period.chars().last()?;
+    let amount = period[..period.len() - unit.len_utf8()]
+        .parse::<i64>()
+        .ok()?;
+    if amount <= 0 {
+        return None;
+    }
+
+    // huge amounts are out of range for a TimeDelta
+    let period = match unit {
+        'h' => TimeDelta::try_hours(amount)?,
+        'd' => TimeDelta::try_days(amount)?,
+        'w' => TimeDelta::try_weeks(amount)?,
+        _ => return None,
+    };
+
+    (period <= TimeDelta::days(MAX_PERIOD_DAYS)).then_some(period)
+}
+
+/// Stores every reading the station products currently list, the products
+/// only go back a few days so this needs to run at least daily.
+pub async fn record_observations(
+    db: &PgPool,
+    observations: &ObservationsAPI,
+) -> Result<(), HistoryError> {
+    let stations = sqlx::query!(
+        r#"SELECT DISTINCT observation_product AS "product!", observation_station AS "station!"
+            FROM locations
+            WHERE observation_product IS NOT NULL AND observation_station IS NOT NULL"#
+    )
+    .fetch_all(db)
+    .await?;
+
+    for station in stations {
+        let readings = match observations
+            .get_all(&station.product, station.station)
+            .await
+        {
+            Ok(readings) => readings,
+            Err(e) => {
+                tracing::error!("error fetching observations for {}: {e}", station.station);
+                continue;
+            }
+        };
+
+        let mut recorded = 0;
+        for reading in readings {
+            let Some(time) = reading.time() else {
+                continue;
+            };
+
+            let inserted = sqlx::query!(
+                r#"INSERT INTO observation_history
+                    (station, observed_at, air_temp, apparent_t, rel_hum, press, wind_dir, wind_spd_kmh, gust_kmh, rain_since_9am)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                    ON CONFLICT (station, observed_at) DO NOTHING"#,
+                station.station,
+                time.naive_utc(),
+                reading.air_temp,
+                reading.apparent_t,
+                reading.rel_hum.map(|v| v as i32),
+                reading.press,
+                reading.wind_dir,
+                reading.wind_spd_kmh.map(|v| v as i32),
+                reading.gust_kmh.map(|v| v as i32),
+                reading.rain_since_9am()
+            )
+            .execute(db)
+            .await?;
+
+            recorded += inserted.rows_affected();
+        }
+
+        tracing::info!("recorded {recorded} observations for {}", station.station);
+    }
+
+    Ok(())
+}
+
+/// Recorded values of a metric for a station, oldest first.
+pub async fn load_history(
+    db: &PgPool,
+    station: i32,
+    metric: Metric,
+    since: DateTime<Utc>,
+) -> Result<Vec<HistoryPoint>, HistoryError> {
+    let rows = sqlx::query!(
+        r#"SELECT observed_at, air_temp, wind_spd_kmh, rain_since_9am FROM observation_history
+            WHERE station = ($1) AND observed_at >= ($2)
+            ORDER BY observed_at"#,
+        station,
+        since.naive_utc()
+    )
+    .fetch_all(db)
+    .await?;
+
+    let points = rows
+        .into_iter()
+        .filter_map(|row| {
+            let value = match metric {
+                Metric::Temp => row.air_temp,
+                Metric::Rain => row.rain_since_9am,
+                Metric::Wind => row.wind_spd_kmh.map(f64::from),
+            }?;
+
+            Some(HistoryPoint {
+                time: row.observed_at.and_utc(),
+                value,
+            })
+        })
+        .collect();
+
+    Ok(points)
+}
+
+pub fn to_csv(metric: Metric, points: &[HistoryPoint]) -> String {
+    let mut csv = format!("time,{}\n", metric.name());
+    for point in points {
+        csv.push_str(&format!("{},{}\n", point.time.to_rfc3339(), point.value));
+    }
+
+    csv
+}
+
+fn draw_line(img: &mut RgbaImage, from: (i64, i64), to: (i64, i64), colour: Rgba<u8>) {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+
+    loop {
+        // two pixels thick so the line survives discord's preview scaling
+        for (px, py) in [(x, y), (x, y + 1)] {
+            if px >= 0 && py >= 0 && px < img.width() as i64 && py < img.height() as i64 {
+                img.put_pixel(px as u32, py as u32, colour);
+            }
+        }
+
+        if (x, y) == to {
+            break;
+        }
+
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Renders a line chart of the points with the time axis in the location's
+/// time zone.
+pub fn render_chart(
+    title: &str,
+    metric: Metric,
+    points: &[HistoryPoint],
+    time_zone: Tz,
+) -> RgbaImage {
+    let mut img = RgbaImage::from_pixel(CHART_WIDTH, CHART_HEIGHT, BACKGROUND);
+    overlay::draw_text(&mut img, MARGIN_LEFT, 12, title, 2, AXIS);
+
+    let (plot_width, plot_height) = (
+        CHART_WIDTH - MARGIN_LEFT - MARGIN_RIGHT,
+        CHART_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM,
+    );
+
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        overlay::draw_text(
+            &mut img,
+            MARGIN_LEFT,
+            MARGIN_TOP + plot_height / 2,
+            "NO OBSERVATIONS RECORDED",
+            2,
+            AXIS,
+        );
+        return img;
+    };
+
+    let (mut min, mut max) = points.iter().fold((f64::MAX, f64::MIN), |(min, max), p| {
+        (min.min(p.value), max.max(p.value))
+    });
+    if metric != Metric::Temp {
+        min = min.min(0.0);
+    }
+    if max - min < 1.0 {
+        max = min + 1.0;
+    }
+
+    let start = first.time;
+    let span = (last.time - start).num_seconds().max(1) as f64;
+
+    let to_x = |time: DateTime<Utc>| {
+        MARGIN_LEFT as i64 + ((time - start).num_seconds() as f64 / span * plot_width as f64) as i64
+    };
+    let to_y = |value: f64| {
+        (MARGIN_TOP + plot_height) as i64
+            - ((value - min) / (max - min) * plot_height as f64) as i64
+    };
+
+    for tick in 0..=Y_TICKS {
+        let value = min + (max - min) * tick as f64 / Y_TICKS as f64;
+        let y = to_y(value) as u32;
+        overlay::fill_rect(&mut img, MARGIN_LEFT, y, plot_width, 1, GRID);
+
+        let label = format!("{value:.1}");
+        let x = MARGIN_LEFT.saturating_sub(overlay::text_width(&label, 1) + 6);
+        overlay::draw_text(&mut img, x, y.saturating_sub(3), &label, 1, AXIS);
+    }
+
+    let unit = metric.unit();
+    overlay::draw_text(&mut img, 8, MARGIN_TOP - 14, unit, 1, AXIS);
+
+    let time_format = if span > TimeDelta::days(2).num_seconds() as f64 {
+        "%d/%m"
+    } else {
+        "%H:%M"
+    };
+    for tick in 0..=X_TICKS {
+        let time = start + TimeDelta::seconds((span * tick as f64 / X_TICKS as f64) as i64);
+        let x = to_x(time) as u32;
+        overlay::fill_rect(&mut img, x, MARGIN_TOP, 1, plot_height, GRID);
+
+        let label = time
+            .with_timezone(&time_zone)
+            .format(time_format)
+            .to_string();
+        let width = overlay::text_width(&label, 1);
+        let x = x.saturating_sub(width / 2).min(CHART_WIDTH - width);
+        overlay::draw_text(&mut img, x, MARGIN_TOP + plot_height + 8, &label, 1, AXIS);
+    }
+
+    overlay::fill_rect(&mut img, MARGIN_LEFT, MARGIN_TOP, 1, plot_height, AXIS);
+    overlay::fill_rect(
+        &mut img,
+        MARGIN_LEFT,
+        MARGIN_TOP + plot_height,
+        plot_width,
+        1,
+        AXIS,
+    );
+
+    for pair in points.windows(2) {
+        if pair[1].time - pair[0].time > TimeDelta::minutes(MAX_GAP_MINUTES) {
+            continue;
+        }
+
+        draw_line(
+            &mut img,
+            (to_x(pair[0].time), to_y(pair[0].value)),
+            (to_x(pair[1].time), to_y(pair[1].value)),
+            metric.colour(),
+        );
+    }
+
+    img
+}
+
+pub fn encode_png(img: &RgbaImage) -> Result<Vec<u8>, HistoryError> {
+    let mut bytes = Vec::new();
+    img.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("24h", Some(TimeDelta::hours(24)))]
+    #[case("7d", Some(TimeDelta::days(7)))]
+    #[case("2w", Some(TimeDelta::weeks(2)))]
+    #[case(" 3D ", Some(TimeDelta::days(3)))]
+    #[case("90d", Some(TimeDelta::days(90)))]
+    #[case("91d", None)]
+    #[case("99999999999999w", None)]
+    #[case("0d", None)]
+    #[case("-1d", None)]
+    #[case("7m", None)]
+    #[case("d", None)]
+    #[case("", None)]
+    fn parses_period(#[case] period: &str, #[case] expected: Option<TimeDelta>) {
+        assert_eq!(parse_period(period), expected);
+    }
+}
@@ -1,16 +1,27 @@
 use crate::{
-    types::{AppError, ForecastEndpointResponse, ForecastForDay},
+    forecast::{forecast_embed, hourly_forecast_embed, ForecastLocation, Forecaster, Provider},
+    history::{HistoryPoint, Metric},
+    mosaic::Mosaic,
+    observations::ObservationsAPI,
+    render::{OutputFormat, RenderOptions},
+    storage::{LocalObjectStore, MemoryObjectStore, ObjectStore, S3ObjectStore},
+    types::{
+        AppError, ForecastEndpointResponse, HistoryEndpointResponse, MarineEndpointResponse,
+        Observation, ObservationsEndpointResponse, RainEndpointResponse, RainSample,
+        RainfallEndpointResponse, WarningsEndpointResponse,
+    },
     willyweather::WillyWeatherAPI,
 };
 use anyhow::Context;
 use axum::{
     extract::{Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     routing::get,
     Json,
 };
-use chrono::{DateTime, Utc};
-use phf::phf_map;
+use chrono::NaiveTime;
+use chrono_tz::Tz;
 use serde::Deserialize;
 use sqlx::postgres::PgPoolOptions;
 use std::{future::IntoFuture, ops::Deref, sync::Arc, time::Duration};
@@ -26,7 +37,13 @@ use twilight_model::{
         command::{CommandOptionChoice, CommandOptionChoiceValue},
         interaction::InteractionContextType,
     },
+    channel::message::Embed,
+    guild::Permissions,
     http::{attachment::Attachment, interaction::InteractionResponseData},
+    id::{
+        marker::{ChannelMarker, GuildMarker, UserMarker},
+        Id,
+    },
     oauth::ApplicationIntegrationType,
     util::Timestamp,
 };
@@ -40,9 +57,29 @@ use vesper::{
     prelude::{AutocompleteContext, DefaultCommandResult, Framework, SlashContext},
 };
 
+mod alerts;
+mod almanac;
 mod background;
 mod bom;
+mod composite;
+mod forecast;
+mod history;
+mod marine;
+mod mosaic;
+mod mp4;
+mod nowcast;
+mod observations;
+mod openmeteo;
+mod overlay;
+mod precis;
+mod radar;
+mod rainfall;
+mod render;
+mod schedule;
+mod source;
+mod storage;
 mod types;
+mod warnings;
 mod willyweather;
 
 #[derive(Clone)]
@@ -58,7 +95,10 @@ impl Deref for BotContext {
 
 struct BotContextInner {
     bom: Arc<bom::BOM>,
-    willyweather: WillyWeatherAPI,
+    forecaster: Forecaster,
+    observations: ObservationsAPI,
+    /// Users allowed to change what's shared by every server.
+    operators: Vec<Id<UserMarker>>,
 }
 
 async fn handle_event(event: Event, _http: Arc<HttpClient>) -> anyhow::Result<()> {
@@ -78,14 +118,69 @@ async fn handle_event(event: Event, _http: Arc<HttpClient>) -> anyhow::Result<()
 
 #[autocomplete]
 async fn autocomplete_location_forecast(
+    ctx: AutocompleteContext<BotContext>,
+) -> Option<InteractionResponseData> {
+    let choices = ForecastLocation::search(ctx.data.forecaster.db(), &ctx.user_input.input)
+        .await
+        .ok()?
+        .into_iter()
+        .map(|(id, name)| CommandOptionChoice {
+            name,
+            name_localizations: None,
+            value: CommandOptionChoiceValue::String(id),
+        })
+        .collect();
+
+    Some(InteractionResponseData {
+        choices: Some(choices),
+        ..Default::default()
+    })
+}
+
+#[autocomplete]
+async fn autocomplete_willyweather_search(
+    ctx: AutocompleteContext<BotContext>,
+) -> Option<InteractionResponseData> {
+    let query = ctx.user_input.input.trim();
+    let locations = if query.len() < 2 {
+        Vec::new()
+    } else {
+        ctx.data
+            .forecaster
+            .willyweather()?
+            .search_locations(query)
+            .await
+            .ok()?
+    };
+
+    let choices = locations
+        .into_iter()
+        .map(|location| CommandOptionChoice {
+            name: format!(
+                "{}, {} {}",
+                location.name, location.state, location.postcode
+            ),
+            name_localizations: None,
+            value: CommandOptionChoiceValue::String(location.id.to_string()),
+        })
+        .collect();
+
+    Some(InteractionResponseData {
+        choices: Some(choices),
+        ..Default::default()
+    })
+}
+
+#[autocomplete]
+async fn autocomplete_forecast_provider(
     _ctx: AutocompleteContext<BotContext>,
 ) -> Option<InteractionResponseData> {
-    let choices = WillyWeatherAPI::get_locations()
+    let choices = [Provider::WillyWeather, Provider::OpenMeteo]
         .into_iter()
-        .map(|item| CommandOptionChoice {
-            name: item.0,
+        .map(|provider| CommandOptionChoice {
+            name: provider.name().to_owned(),
             name_localizations: None,
-            value: CommandOptionChoiceValue::String(item.1),
+            value: CommandOptionChoiceValue::String(provider.name().to_owned()),
         })
         .collect();
 
@@ -99,15 +194,99 @@ async fn autocomplete_location_forecast(
 async fn autocomplete_location(
     ctx: AutocompleteContext<BotContext>,
 ) -> Option<InteractionResponseData> {
-    let choices = sqlx::query!(r#"SELECT * FROM locations"#)
-        .fetch_all(ctx.data.bom.db())
+    let choices = sqlx::query!(
+        r#"SELECT name, bom_radar_id FROM locations
+            WHERE active AND (name ILIKE $1 || '%' OR bom_radar_id ILIKE $1 || '%')
+            ORDER BY priority DESC, name
+            LIMIT 25"#,
+        ctx.user_input.input.trim()
+    )
+    .fetch_all(ctx.data.bom.db())
+    .await
+    .ok()?
+    .into_iter()
+    .map(|item| CommandOptionChoice {
+        name: item.name,
+        name_localizations: None,
+        value: CommandOptionChoiceValue::String(item.bom_radar_id.to_string()),
+    })
+    .collect();
+
+    Some(InteractionResponseData {
+        choices: Some(choices),
+        ..Default::default()
+    })
+}
+
+#[autocomplete]
+async fn autocomplete_mosaic_region(
+    ctx: AutocompleteContext<BotContext>,
+) -> Option<InteractionResponseData> {
+    let choices = Mosaic::regions(ctx.data.bom.db())
         .await
         .ok()?
         .into_iter()
-        .map(|item| CommandOptionChoice {
-            name: item.name,
+        .map(|(region, name)| CommandOptionChoice {
+            name,
+            name_localizations: None,
+            value: CommandOptionChoiceValue::String(region),
+        })
+        .collect();
+
+    Some(InteractionResponseData {
+        choices: Some(choices),
+        ..Default::default()
+    })
+}
+
+#[autocomplete]
+async fn autocomplete_format(
+    _ctx: AutocompleteContext<BotContext>,
+) -> Option<InteractionResponseData> {
+    let choices = OutputFormat::ALL
+        .into_iter()
+        .map(|format| CommandOptionChoice {
+            name: format.name().to_owned(),
+            name_localizations: None,
+            value: CommandOptionChoiceValue::String(format.name().to_owned()),
+        })
+        .collect();
+
+    Some(InteractionResponseData {
+        choices: Some(choices),
+        ..Default::default()
+    })
+}
+
+#[autocomplete]
+async fn autocomplete_state(
+    _ctx: AutocompleteContext<BotContext>,
+) -> Option<InteractionResponseData> {
+    let choices = warnings::State::ALL
+        .into_iter()
+        .map(|state| CommandOptionChoice {
+            name: state.code().to_owned(),
+            name_localizations: None,
+            value: CommandOptionChoiceValue::String(state.code().to_owned()),
+        })
+        .collect();
+
+    Some(InteractionResponseData {
+        choices: Some(choices),
+        ..Default::default()
+    })
+}
+
+#[autocomplete]
+async fn autocomplete_metric(
+    _ctx: AutocompleteContext<BotContext>,
+) -> Option<InteractionResponseData> {
+    let choices = Metric::ALL
+        .into_iter()
+        .map(|metric| CommandOptionChoice {
+            name: metric.description().to_owned(),
             name_localizations: None,
-            value: CommandOptionChoiceValue::String(item.bom_radar_id.to_string()),
+            value: CommandOptionChoiceValue::String(metric.name().to_owned()),
         })
         .collect();
 
@@ -117,6 +296,181 @@ async fn autocomplete_location(
     })
 }
 
+fn parse_metric(metric: Option<&str>) -> anyhow::Result<Metric> {
+    match metric {
+        Some(metric) => {
+            Metric::from_name(metric).with_context(|| format!("unknown metric: {metric}"))
+        }
+        None => Ok(Metric::Temp),
+    }
+}
+
+fn parse_period(period: Option<&str>) -> anyhow::Result<chrono::TimeDelta> {
+    let period = period.unwrap_or("7d");
+    history::parse_period(period).with_context(|| {
+        format!(
+            "invalid period: {period}, use something like 24h, 7d or 2w up to {} days",
+            history::MAX_PERIOD_DAYS
+        )
+    })
+}
+
+fn hourly_forecast_hours(hours: Option<i64>) -> i64 {
+    hours
+        .unwrap_or(forecast::DEFAULT_HOURS)
+        .clamp(1, forecast::MAX_HOURS)
+}
+
+fn parse_state(state: &str) -> anyhow::Result<warnings::State> {
+    warnings::State::from_code(state).with_context(|| format!("unknown state: {state}"))
+}
+
+/// Swaps a radar product for the same site at another range.
+async fn radar_product_for_range(
+    ctx: &BotContext,
+    bom_radar_id: String,
+    range: Option<i64>,
+) -> anyhow::Result<String> {
+    match range {
+        Some(range) => Ok(ctx
+            .bom
+            .get_radar_product_for_range(&bom_radar_id, range as i32)
+            .await?),
+        None => Ok(bom_radar_id),
+    }
+}
+
+/// Radars can be picked by product id or name, anything else is treated as a
+/// place and the best radar for it is used. Returns a title and the product.
+async fn resolve_radar(ctx: &BotContext, location: &str) -> anyhow::Result<(String, String)> {
+    let registered = sqlx::query!(
+        r#"SELECT name, bom_radar_id FROM locations
+            WHERE active AND (bom_radar_id = UPPER($1) OR name ILIKE $1)
+            LIMIT 1"#,
+        location.trim()
+    )
+    .fetch_optional(ctx.bom.db())
+    .await?;
+
+    if let Some(radar) = registered {
+        return Ok((radar.name, radar.bom_radar_id));
+    }
+
+    let (place, latitude, longitude) = match radar::parse_coordinates(location) {
+        Some((latitude, longitude)) => (
+            format!("{latitude:.3}, {longitude:.3}"),
+            latitude,
+            longitude,
+        ),
+        None => place_coordinates(ctx, location).await?,
+    };
+
+    let site = ctx.bom.get_best_radar_for(latitude, longitude).await?;
+    Ok((format!("{place} ({})", site.name), site.bom_radar_id))
+}
+
+/// Forecast locations already have coordinates, other suburbs and postcodes
+/// are searched on WillyWeather.
+async fn place_coordinates(ctx: &BotContext, place: &str) -> anyhow::Result<(String, f64, f64)> {
+    let db = ctx.forecaster.db();
+    if let Some((id, _)) = ForecastLocation::search(db, place)
+        .await?
+        .into_iter()
+        .next()
+    {
+        let location = ForecastLocation::get(db, &id).await?;
+        if let (Some(latitude), Some(longitude)) = (location.latitude, location.longitude) {
+            return Ok((location.name, latitude, longitude));
+        }
+    }
+
+    let willyweather = ctx
+        .forecaster
+        .willyweather()
+        .with_context(|| format!("{place} isn't a radar and willyweather isn't configured"))?;
+    let location = willyweather
+        .search_locations(place)
+        .await?
+        .into_iter()
+        .next()
+        .with_context(|| format!("couldn't find {place}"))?;
+
+    Ok((
+        format!("{}, {}", location.name, location.state),
+        location.lat,
+        location.lng,
+    ))
+}
+
+/// Radar products look like `IDR703`, the site number then the range.
+fn parse_radar_product(bom_radar_id: &str) -> anyhow::Result<(String, i32)> {
+    let bom_radar_id = bom_radar_id.trim().to_uppercase();
+    let range_km = radar::range_for_product(&bom_radar_id)
+        .with_context(|| format!("{bom_radar_id} isn't a radar product like IDR703"))?;
+
+    Ok((bom_radar_id, range_km))
+}
+
+/// A comma separated list of ranges like `128,256,512`, always including the
+/// product's own range.
+fn parse_radar_ranges(ranges: &str, bom_radar_id: &str, range_km: i32) -> anyhow::Result<Vec<i32>> {
+    let mut ranges = ranges
+        .split(',')
+        .map(|range| {
+            let range = range.trim().trim_end_matches("km");
+            range
+                .parse::<i32>()
+                .ok()
+                .filter(|range| radar::product_for_range(bom_radar_id, *range).is_some())
+                .with_context(|| format!("{range} isn't a radar range, use 64, 128, 256 or 512"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    ranges.push(range_km);
+    ranges.sort();
+    ranges.dedup();
+
+    Ok(ranges)
+}
+
+fn format_ranges(ranges: &[i32]) -> String {
+    ranges
+        .iter()
+        .map(i32::to_string)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parse_output_format(format: Option<String>) -> anyhow::Result<OutputFormat> {
+    match format {
+        Some(name) => {
+            OutputFormat::from_name(&name).with_context(|| format!("unknown format: {name}"))
+        }
+        None => Ok(OutputFormat::default()),
+    }
+}
+
+/// Attaches a rendered loop, images are shown in the embed but videos can only
+/// be sent alongside it.
+fn attach_loop(embed: EmbedBuilder, bytes: Vec<u8>, format: OutputFormat) -> (Embed, Attachment) {
+    let filename = format!("url.{}", format.extension());
+
+    let embed = if format.is_video() {
+        embed
+    } else {
+        match ImageSource::attachment(&filename) {
+            Ok(image) => embed.image(image),
+            Err(e) => {
+                tracing::error!("error with image url: {e}");
+                embed
+            }
+        }
+    };
+
+    let attachment = Attachment::from_bytes(filename, bytes, 1);
+    (embed.build(), attachment)
+}
+
 #[error_handler]
 async fn handle_interaction_error(ctx: &mut SlashContext<BotContext>, error: DefaultError) {
     let fut = async {
@@ -156,6 +510,14 @@ async fn timelapse(
     #[autocomplete(autocomplete_location)]
     #[description = "pick a location"]
     location: Option<String>,
+    #[description = "number of frames to show"] frames: Option<i64>,
+    #[description = "delay between frames in ms"] delay: Option<i64>,
+    #[description = "extra ms to hold the last frame"] hold: Option<i64>,
+    #[description = "times to play the loop, 0 plays forever"] loops: Option<i64>,
+    #[autocomplete(autocomplete_format)]
+    #[description = "gif, webp, apng or mp4"]
+    format: Option<String>,
+    #[description = "zoom to 64, 128, 256 or 512 km"] range: Option<i64>,
 ) -> DefaultCommandResult {
     ctx.defer(false).await?;
 
@@ -167,8 +529,15 @@ async fn timelapse(
     )
     .fetch_one(ctx.data.bom.db())
     .await?;
-
-    let (url, bytes) = ctx.data.bom.get_radar_timelapse_24hr_for(&location).await?;
+    let location = radar_product_for_range(&ctx.data, location, range).await?;
+
+    let options = RenderOptions::TIMELAPSE.with_overrides(frames, delay, hold, loops);
+    let format = parse_output_format(format)?;
+    let (url, bytes) = ctx
+        .data
+        .bom
+        .get_radar_timelapse_24hr_for(&location, &options, format)
+        .await?;
 
     let now = chrono::offset::Utc::now().naive_utc();
     let embed = EmbedBuilder::new()
@@ -182,18 +551,7 @@ async fn timelapse(
 
     tracing::info!("using url: {url}");
 
-    let image = ImageSource::attachment("url.gif");
-
-    let embed = match image {
-        Ok(image) => embed.image(image),
-        Err(e) => {
-            tracing::error!("error with image url: {e}");
-            embed
-        }
-    }
-    .build();
-
-    let attachment = Attachment::from_bytes("url.gif".to_owned(), bytes, 1);
+    let (embed, attachment) = attach_loop(embed, bytes, format);
     ctx.interaction_client
         .update_response(&ctx.interaction.token)
         .embeds(Some(&[embed]))
@@ -206,7 +564,16 @@ async fn timelapse(
 #[command]
 #[description = "get satellite images from bom"]
 #[error_handler(handle_interaction_error)]
-async fn satellite(ctx: &mut SlashContext<BotContext>) -> DefaultCommandResult {
+async fn satellite(
+    ctx: &mut SlashContext<BotContext>,
+    #[description = "number of frames to show"] frames: Option<i64>,
+    #[description = "delay between frames in ms"] delay: Option<i64>,
+    #[description = "extra ms to hold the last frame"] hold: Option<i64>,
+    #[description = "times to play the loop, 0 plays forever"] loops: Option<i64>,
+    #[autocomplete(autocomplete_format)]
+    #[description = "gif, webp, apng or mp4"]
+    format: Option<String>,
+) -> DefaultCommandResult {
     ctx.defer(false).await?;
 
     // some satellite
@@ -218,7 +585,13 @@ async fn satellite(ctx: &mut SlashContext<BotContext>) -> DefaultCommandResult {
     .fetch_one(ctx.data.bom.db())
     .await?;
 
-    let (url, bytes) = ctx.data.bom.get_latest_satellite_gif_for(location).await?;
+    let options = RenderOptions::SATELLITE.with_overrides(frames, delay, hold, loops);
+    let format = parse_output_format(format)?;
+    let (url, bytes) = ctx
+        .data
+        .bom
+        .get_latest_satellite_gif_for(location, &options, format)
+        .await?;
 
     let now = chrono::offset::Utc::now().naive_utc();
     let embed = EmbedBuilder::new()
@@ -232,18 +605,7 @@ async fn satellite(ctx: &mut SlashContext<BotContext>) -> DefaultCommandResult {
 
     tracing::info!("using url: {url}");
 
-    let image = ImageSource::attachment("url.gif");
-
-    let embed = match image {
-        Ok(image) => embed.image(image),
-        Err(e) => {
-            tracing::error!("error with image url: {e}");
-            embed
-        }
-    }
-    .build();
-
-    let attachment = Attachment::from_bytes("url.gif".to_owned(), bytes, 1);
+    let (embed, attachment) = attach_loop(embed, bytes, format);
     ctx.interaction_client
         .update_response(&ctx.interaction.token)
         .embeds(Some(&[embed]))
@@ -259,25 +621,82 @@ async fn satellite(ctx: &mut SlashContext<BotContext>) -> DefaultCommandResult {
 async fn radar(
     ctx: &mut SlashContext<BotContext>,
     #[autocomplete(autocomplete_location)]
-    #[description = "pick a location"]
+    #[description = "pick a radar or type a suburb, postcode or coordinates"]
     location: Option<String>,
+    #[description = "number of frames to show"] frames: Option<i64>,
+    #[description = "delay between frames in ms"] delay: Option<i64>,
+    #[description = "extra ms to hold the last frame"] hold: Option<i64>,
+    #[description = "times to play the loop, 0 plays forever"] loops: Option<i64>,
+    #[autocomplete(autocomplete_format)]
+    #[description = "gif, webp, apng or mp4"]
+    format: Option<String>,
+    #[description = "add predicted frames for the next hour"] nowcast: Option<bool>,
+    #[description = "zoom to 64, 128, 256 or 512 km"] range: Option<i64>,
 ) -> DefaultCommandResult {
     ctx.defer(false).await?;
 
     // perth
-    let location = location.unwrap_or_else(|| "IDR703".to_owned());
-    let location_name = sqlx::query!(
-        "SELECT name FROM locations WHERE bom_radar_id = ($1)",
-        location
-    )
-    .fetch_one(ctx.data.bom.db())
-    .await?;
+    let (title, location) =
+        resolve_radar(&ctx.data, location.as_deref().unwrap_or("IDR703")).await?;
+    let location = radar_product_for_range(&ctx.data, location, range).await?;
+
+    let options = RenderOptions::RADAR.with_overrides(frames, delay, hold, loops);
+    let format = parse_output_format(format)?;
+    let (url, bytes) = ctx
+        .data
+        .bom
+        .generate_radar_gif_for(&location, &options, format, nowcast.unwrap_or(false))
+        .await?;
+
+    let now = chrono::offset::Utc::now().naive_utc();
+    let embed = EmbedBuilder::new().title(title).color(0x003366).timestamp(
+        Timestamp::from_secs(now.and_utc().timestamp())
+            .context("must have valid time")
+            .unwrap(),
+    );
+
+    tracing::info!("using url: {url}");
+
+    let (embed, attachment) = attach_loop(embed, bytes, format);
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .attachments(&[attachment])
+        .embeds(Some(&[embed]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("radar-mosaic")]
+#[description = "get the radar mosaic for australia or a state"]
+#[error_handler(handle_interaction_error)]
+async fn radar_mosaic(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_mosaic_region)]
+    #[description = "pick a region, defaults to all of australia"]
+    region: Option<String>,
+    #[description = "number of frames to show"] frames: Option<i64>,
+    #[description = "delay between frames in ms"] delay: Option<i64>,
+    #[description = "extra ms to hold the last frame"] hold: Option<i64>,
+    #[description = "times to play the loop, 0 plays forever"] loops: Option<i64>,
+    #[autocomplete(autocomplete_format)]
+    #[description = "gif, webp, apng or mp4"]
+    format: Option<String>,
+) -> DefaultCommandResult {
+    ctx.defer(false).await?;
 
-    let (url, bytes) = ctx.data.bom.generate_radar_gif_for(&location).await?;
+    let mosaic = Mosaic::get(ctx.data.bom.db(), region.as_deref().unwrap_or("AU")).await?;
+    let options = RenderOptions::RADAR.with_overrides(frames, delay, hold, loops);
+    let format = parse_output_format(format)?;
+    let (url, bytes) = ctx
+        .data
+        .bom
+        .generate_mosaic_gif_for(&mosaic, &options, format)
+        .await?;
 
     let now = chrono::offset::Utc::now().naive_utc();
     let embed = EmbedBuilder::new()
-        .title(location_name.name)
+        .title(format!("{} radar mosaic", mosaic.name))
         .color(0x003366)
         .timestamp(
             Timestamp::from_secs(now.and_utc().timestamp())
@@ -287,18 +706,7 @@ async fn radar(
 
     tracing::info!("using url: {url}");
 
-    let image = ImageSource::attachment("url.gif");
-
-    let embed = match image {
-        Ok(image) => embed.image(image),
-        Err(e) => {
-            tracing::error!("error in radar embed {e}");
-            embed
-        }
-    }
-    .build();
-
-    let attachment = Attachment::from_bytes("url.gif".to_owned(), bytes, 1);
+    let (embed, attachment) = attach_loop(embed, bytes, format);
     ctx.interaction_client
         .update_response(&ctx.interaction.token)
         .attachments(&[attachment])
@@ -308,96 +716,136 @@ async fn radar(
     Ok(())
 }
 
-const PRECIS_TO_EMOJI: phf::Map<&'static str, &'static str> = phf_map! {
-    "fine" => "☀️",
-    "mostly-fine" => "🌤️",
-    "high-cloud" => "☁️",
-    "partly-cloudy" => "⛅",
-    "mostly-cloudy" => "🌥️",
-    "cloudy" => "☁️",
-    "overcast" => "🌫️",
-    "shower-or-two" => "🌦️",
-    "chance-shower-fine" => "🌧️",
-    "chance-shower-cloud" => "🌧️",
-    "drizzle" => "🌧️",
-    "few-showers" => "🌦️",
-    "showers-rain" => "🌧️",
-    "heavy-showers-rain" => "🌧️",
-    "chance-thunderstorm-fine" => "⛈️",
-    "chance-thunderstorm-cloud" => "⛈️",
-    "chance-thunderstorm-showers" => "⛈️",
-    "thunderstorm" => "⛈️",
-    "chance-snow-fine" => "🌨️",
-    "chance-snow-cloud" => "🌨️",
-    "snow-and-rain" => "🌨️",
-    "light-snow" => "🌨️",
-    "snow" => "❄️",
-    "heavy-snow" => "🌨️",
-    "wind" => "💨",
-    "frost" => "🧊",
-    "fog" => "🌁",
-    "hail" => "🌨️",
-    "dust" => "🌪️",
-};
-
-#[command]
-#[description = "get forecast information from bom"]
+#[command("rain-now")]
+#[description = "is it raining right now"]
 #[error_handler(handle_interaction_error)]
-async fn forecast(
+async fn rain_now(
     ctx: &mut SlashContext<BotContext>,
-    #[autocomplete(autocomplete_location_forecast)]
-    #[description = "pick a location"]
+    #[autocomplete(autocomplete_location)]
+    #[description = "pick a radar, its centre is used when no coordinates are given"]
     location: Option<String>,
-    #[description = "number of days"] days: Option<i64>,
+    #[description = "latitude of the point to check"] latitude: Option<f64>,
+    #[description = "longitude of the point to check"] longitude: Option<f64>,
 ) -> DefaultCommandResult {
     ctx.defer(false).await?;
 
-    // perth
-    let location = location.unwrap_or_else(|| WillyWeatherAPI::PERTH_ID.to_owned());
-    let days = days.unwrap_or(7);
+    let (latitude, longitude) = match (latitude, longitude) {
+        (Some(latitude), Some(longitude)) => (latitude, longitude),
+        _ => {
+            // perth
+            let location = location.unwrap_or_else(|| "IDR703".to_owned());
+            let site = ctx.data.bom.get_radar_site(&location).await?;
+            (site.latitude, site.longitude)
+        }
+    };
 
-    let forecast = ctx.data.willyweather.get_forecast(&location, &days).await?;
+    let rain = ctx.data.bom.get_rain_at(latitude, longitude).await?;
+    let (time, rate) = rain.current().context("must have a frame")?;
 
-    let mut embed = EmbedBuilder::new()
-        .title(format!("🌡️ Forecast for {}", forecast.location.name))
+    let description = if rate > 0.0 {
+        format!(
+            "🌧️ {} rain, around {rate} mm/h and {} over the last hour",
+            rainfall::intensity(rate),
+            rain.trend().name()
+        )
+    } else if let Some(minutes) = rain.expected_in_minutes {
+        format!("🌦️ Not raining yet, rain expected in ~{minutes} minutes")
+    } else {
+        "☀️ Not raining".to_owned()
+    };
+
+    let embed = EmbedBuilder::new()
+        .title(format!("Rain near {latitude:.3}, {longitude:.3}"))
+        .description(description)
         .color(0x003366)
-        .footer(EmbedFooterBuilder::new(
-            "BOM charges $4,037.00 for this data",
-        ));
-
-    for (i, days) in forecast.forecasts.weather.days.into_iter().enumerate() {
-        let entry = days.entries.first().context("must have entries")?;
-        let min = entry.min;
-        let max = entry.max;
-        let description = &entry.precis;
-        let datetime_with_timezone = &format!("{} +0800", entry.date_time);
-        let datetime = DateTime::parse_from_str(datetime_with_timezone, "%Y-%m-%d %H:%M:%S %z")?;
-        let emoji = PRECIS_TO_EMOJI.get(&entry.precis_code).map_or("", |e| e);
-        let uv_level = forecast.forecasts.uv.days.get(i).map(|e| &e.alert);
-
-        let formatted_date = if datetime.date_naive() == Utc::now().date_naive() {
-            "Today".to_owned()
-        } else {
-            datetime.format("%A %d/%m").to_string()
-        };
+        .footer(EmbedFooterBuilder::new(format!("from {}", rain.site.name)))
+        .timestamp(
+            Timestamp::from_secs(time.timestamp())
+                .context("must have valid time")
+                .unwrap(),
+        );
 
-        let temperature_details = if let Some(uv_level) = uv_level {
-            format!(
-                "**Max:** {}°c, **Min:** {}°c, **UV:** {:.1}",
-                max, min, uv_level.max_index
-            )
-        } else {
-            format!("**Max:** {}°c, **Min:** {}°c", max, min)
-        };
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
 
-        embed = embed.field(
-            EmbedFieldBuilder::new(
-                formatted_date,
-                format!("{} — {} {}", temperature_details, emoji, description),
+    Ok(())
+}
+
+#[command("subscribe")]
+#[description = "get a dm when rain gets close"]
+#[error_handler(handle_interaction_error)]
+async fn alerts_subscribe(
+    ctx: &mut SlashContext<BotContext>,
+    #[description = "lowest rain rate in mm/h to alert on"] threshold: f64,
+    #[autocomplete(autocomplete_location)]
+    #[description = "pick a radar, its centre is used when no coordinates are given"]
+    location: Option<String>,
+    #[description = "how close the rain has to be in km, defaults to 10"] radius: Option<f64>,
+    #[description = "latitude of the point to watch"] latitude: Option<f64>,
+    #[description = "longitude of the point to watch"] longitude: Option<f64>,
+    #[description = "minutes to wait between alerts, defaults to 120"] cooldown: Option<i64>,
+    #[description = "hour alerts stop for the night, e.g. 22"] quiet_start: Option<i64>,
+    #[description = "hour alerts start again, e.g. 7"] quiet_end: Option<i64>,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    let user_id = ctx.interaction.author_id().context("must have a user")?;
+    let (site, latitude, longitude) = match (location, latitude, longitude) {
+        (_, Some(latitude), Some(longitude)) => {
+            let site = ctx
+                .data
+                .bom
+                .get_nearest_radar_site(latitude, longitude)
+                .await?;
+            (site, latitude, longitude)
+        }
+        (Some(location), _, _) => {
+            let site = ctx.data.bom.get_radar_site(&location).await?;
+            let (latitude, longitude) = (site.latitude, site.longitude);
+            (site, latitude, longitude)
+        }
+        _ => {
+            return Err(
+                anyhow::anyhow!("pick a radar or give both a latitude and longitude").into(),
             )
-            .build(),
-        )
-    }
+        }
+    };
+
+    let time_zone = ctx.data.bom.radar_time_zone(&site.bom_radar_id).await?;
+    let threshold = threshold.max(rainfall::MIN_RAIN_RATE as f64);
+    let radius = radius.unwrap_or(10.0).clamp(1.0, 100.0);
+    let cooldown = cooldown.unwrap_or(120).clamp(10, 1440) as i32;
+    let quiet_start = quiet_start.map(|h| h.clamp(0, 23) as i32);
+    let quiet_end = quiet_end.map(|h| h.clamp(0, 23) as i32);
+
+    let alert = sqlx::query!(
+        r#"INSERT INTO rain_alerts
+            (user_id, bom_radar_id, latitude, longitude, radius_km, threshold, cooldown_minutes, quiet_start_hour, quiet_end_hour, time_zone)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id"#,
+        user_id.get() as i64,
+        site.bom_radar_id,
+        latitude,
+        longitude,
+        radius,
+        threshold,
+        cooldown,
+        quiet_start,
+        quiet_end,
+        time_zone.name()
+    )
+    .fetch_one(ctx.data.bom.db())
+    .await?;
+
+    let embed = EmbedBuilder::new()
+        .title(format!("🔔 Rain alert #{} set", alert.id))
+        .description(format!(
+            "You'll get a dm when {threshold} mm/h or more is within {radius} km of {latitude:.3}, {longitude:.3}"
+        ))
+        .color(0x003366)
+        .footer(EmbedFooterBuilder::new(format!("using {}", site.name)));
 
     ctx.interaction_client
         .update_response(&ctx.interaction.token)
@@ -407,17 +855,1048 @@ async fn forecast(
     Ok(())
 }
 
-async fn health(_ctx: State<BotContext>) -> StatusCode {
-    StatusCode::NO_CONTENT
-}
+#[command("list")]
+#[description = "list your rain alerts"]
+#[error_handler(handle_interaction_error)]
+async fn alerts_list(ctx: &mut SlashContext<BotContext>) -> DefaultCommandResult {
+    ctx.defer(true).await?;
 
-#[derive(Deserialize)]
-struct ForecastParams {
-    location: Option<String>,
-}
+    let user_id = ctx.interaction.author_id().context("must have a user")?;
+    let alerts = sqlx::query!(
+        "SELECT * FROM rain_alerts WHERE user_id = ($1) ORDER BY id",
+        user_id.get() as i64
+    )
+    .fetch_all(ctx.data.bom.db())
+    .await?;
 
-async fn forecast_endpoint(
-    ctx: State<BotContext>,
+    let mut embed = EmbedBuilder::new().title("🔔 Rain alerts").color(0x003366);
+
+    if alerts.is_empty() {
+        embed = embed.description("You have no rain alerts");
+    }
+
+    for alert in alerts {
+        let quiet_hours = match (alert.quiet_start_hour, alert.quiet_end_hour) {
+            (Some(start), Some(end)) => format!(", quiet from {start}:00 to {end}:00"),
+            _ => String::new(),
+        };
+
+        embed = embed.field(
+            EmbedFieldBuilder::new(
+                format!(
+                    "#{} near {:.3}, {:.3}",
+                    alert.id, alert.latitude, alert.longitude
+                ),
+                format!(
+                    "{} mm/h within {} km, every {} minutes at most{quiet_hours}",
+                    alert.threshold, alert.radius_km, alert.cooldown_minutes
+                ),
+            )
+            .build(),
+        );
+    }
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("unsubscribe")]
+#[description = "stop a rain alert"]
+#[error_handler(handle_interaction_error)]
+async fn alerts_unsubscribe(
+    ctx: &mut SlashContext<BotContext>,
+    #[description = "alert number from /alerts list"] id: i64,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    let user_id = ctx.interaction.author_id().context("must have a user")?;
+    let deleted = sqlx::query!(
+        "DELETE FROM rain_alerts WHERE id = ($1) AND user_id = ($2)",
+        id as i32,
+        user_id.get() as i64
+    )
+    .execute(ctx.data.bom.db())
+    .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(anyhow::anyhow!("you don't have an alert #{id}").into());
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(format!("🔕 Rain alert #{id} removed"))
+        .color(0x003366);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+/// Schedules and subscriptions post into guild channels, so only people who
+/// can manage the guild can change them.
+fn require_manage_guild(ctx: &SlashContext<BotContext>) -> anyhow::Result<Id<GuildMarker>> {
+    let guild_id = ctx
+        .interaction
+        .guild_id
+        .context("this can only be used in a server")?;
+
+    let can_manage = ctx
+        .interaction
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.contains(Permissions::MANAGE_GUILD));
+
+    if !can_manage {
+        anyhow::bail!("you need the manage server permission to do this");
+    }
+
+    Ok(guild_id)
+}
+
+/// Forecast locations and radars are shared by every server, so only the bot's
+/// operators (`OPERATOR_USER_IDS`) can change them.
+fn require_operator(ctx: &SlashContext<BotContext>) -> anyhow::Result<()> {
+    let is_operator = ctx
+        .interaction
+        .author_id()
+        .is_some_and(|id| ctx.data.operators.contains(&id));
+
+    if !is_operator {
+        anyhow::bail!("only the bot's operators can do this");
+    }
+
+    Ok(())
+}
+
+fn parse_post_time(time: &str) -> anyhow::Result<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .with_context(|| format!("time should look like 06:30, got {time}"))
+}
+
+fn parse_time_zone(time_zone: &str) -> anyhow::Result<Tz> {
+    time_zone
+        .parse()
+        .map_err(|_| anyhow::anyhow!("unknown time zone: {time_zone}"))
+}
+
+#[command("forecast")]
+#[description = "post the forecast to a channel every day"]
+#[error_handler(handle_interaction_error)]
+async fn schedule_forecast(
+    ctx: &mut SlashContext<BotContext>,
+    #[description = "channel to post in"] channel: Id<ChannelMarker>,
+    #[description = "time to post, e.g. 06:30"] time: String,
+    #[autocomplete(autocomplete_location_forecast)]
+    #[description = "pick a location"]
+    location: Option<String>,
+    #[description = "number of days"] days: Option<i64>,
+    #[description = "time zone, defaults to Australia/Perth"] time_zone: Option<String>,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    let guild_id = require_manage_guild(ctx)?;
+    let user_id = ctx.interaction.author_id().context("must have a user")?;
+    let post_time = parse_post_time(&time)?;
+    let time_zone = parse_time_zone(time_zone.as_deref().unwrap_or("Australia/Perth"))?;
+    // perth
+    let location = location.unwrap_or_else(|| WillyWeatherAPI::PERTH_ID.to_owned());
+    let days = days.unwrap_or(7).clamp(1, 7) as i32;
+
+    let schedule = sqlx::query!(
+        r#"INSERT INTO forecast_schedules
+            (guild_id, channel_id, location, days, post_time, time_zone, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id"#,
+        guild_id.get() as i64,
+        channel.get() as i64,
+        location,
+        days,
+        post_time,
+        time_zone.name(),
+        user_id.get() as i64
+    )
+    .fetch_one(ctx.data.bom.db())
+    .await?;
+
+    let embed = EmbedBuilder::new()
+        .title(format!("🗓️ Forecast schedule #{} created", schedule.id))
+        .description(format!(
+            "Posting in <#{channel}> every day at {} {}",
+            post_time.format("%H:%M"),
+            time_zone.name()
+        ))
+        .color(0x003366);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("edit")]
+#[description = "change a scheduled forecast"]
+#[error_handler(handle_interaction_error)]
+async fn schedule_edit(
+    ctx: &mut SlashContext<BotContext>,
+    #[description = "schedule number from /schedule list"] id: i64,
+    #[description = "channel to post in"] channel: Option<Id<ChannelMarker>>,
+    #[description = "time to post, e.g. 06:30"] time: Option<String>,
+    #[autocomplete(autocomplete_location_forecast)]
+    #[description = "pick a location"]
+    location: Option<String>,
+    #[description = "number of days"] days: Option<i64>,
+    #[description = "time zone, e.g. Australia/Perth"] time_zone: Option<String>,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    let guild_id = require_manage_guild(ctx)?;
+    let post_time = time.as_deref().map(parse_post_time).transpose()?;
+    let time_zone = time_zone.as_deref().map(parse_time_zone).transpose()?;
+
+    let updated = sqlx::query!(
+        r#"UPDATE forecast_schedules SET
+            channel_id = COALESCE($3, channel_id),
+            location = COALESCE($4, location),
+            days = COALESCE($5, days),
+            post_time = COALESCE($6, post_time),
+            time_zone = COALESCE($7, time_zone),
+            last_posted_on = CASE
+                WHEN post_time IS DISTINCT FROM COALESCE($6, post_time)
+                    OR time_zone IS DISTINCT FROM COALESCE($7, time_zone)
+                THEN NULL
+                ELSE last_posted_on
+            END
+            WHERE id = ($1) AND guild_id = ($2)"#,
+        id as i32,
+        guild_id.get() as i64,
+        channel.map(|c| c.get() as i64),
+        location,
+        days.map(|d| d.clamp(1, 7) as i32),
+        post_time,
+        time_zone.map(|tz| tz.name())
+    )
+    .execute(ctx.data.bom.db())
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(anyhow::anyhow!("this server doesn't have a schedule #{id}").into());
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(format!("🗓️ Forecast schedule #{id} updated"))
+        .color(0x003366);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("delete")]
+#[description = "stop a scheduled forecast"]
+#[error_handler(handle_interaction_error)]
+async fn schedule_delete(
+    ctx: &mut SlashContext<BotContext>,
+    #[description = "schedule number from /schedule list"] id: i64,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    let guild_id = require_manage_guild(ctx)?;
+    let deleted = sqlx::query!(
+        "DELETE FROM forecast_schedules WHERE id = ($1) AND guild_id = ($2)",
+        id as i32,
+        guild_id.get() as i64
+    )
+    .execute(ctx.data.bom.db())
+    .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(anyhow::anyhow!("this server doesn't have a schedule #{id}").into());
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(format!("🗓️ Forecast schedule #{id} deleted"))
+        .color(0x003366);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("list")]
+#[description = "list scheduled forecasts for this server"]
+#[error_handler(handle_interaction_error)]
+async fn schedule_list(ctx: &mut SlashContext<BotContext>) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    let guild_id = ctx
+        .interaction
+        .guild_id
+        .context("schedules can only be used in a server")?;
+
+    let schedules = sqlx::query!(
+        r#"SELECT s.*, l.name AS "location_name?" FROM forecast_schedules s
+            LEFT JOIN forecast_locations l ON l.id = s.location
+            WHERE s.guild_id = ($1)
+            ORDER BY s.id"#,
+        guild_id.get() as i64
+    )
+    .fetch_all(ctx.data.bom.db())
+    .await?;
+
+    let mut embed = EmbedBuilder::new()
+        .title("🗓️ Forecast schedules")
+        .color(0x003366);
+
+    if schedules.is_empty() {
+        embed = embed.description("This server has no forecast schedules");
+    }
+
+    for schedule in schedules {
+        let location = schedule
+            .location_name
+            .as_deref()
+            .unwrap_or(schedule.location.as_str());
+
+        embed = embed.field(
+            EmbedFieldBuilder::new(
+                format!("#{} — {location}", schedule.id),
+                format!(
+                    "<#{}> at {} {}, {} days",
+                    schedule.channel_id,
+                    schedule.post_time.format("%H:%M"),
+                    schedule.time_zone,
+                    schedule.days
+                ),
+            )
+            .build(),
+        );
+    }
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("warnings")]
+#[description = "get active bom warnings"]
+#[error_handler(handle_interaction_error)]
+async fn warnings_command(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_state)]
+    #[description = "pick a state"]
+    state: Option<String>,
+) -> DefaultCommandResult {
+    ctx.defer(false).await?;
+
+    let state = parse_state(state.as_deref().unwrap_or("WA"))?;
+    let active = warnings::active_warnings(ctx.data.bom.db(), Some(state)).await?;
+
+    let mut embed = EmbedBuilder::new()
+        .title(format!("⚠️ Warnings for {}", state.code()))
+        .color(0xcc6666);
+
+    if active.is_empty() {
+        embed = embed.description("There are no active warnings");
+    }
+
+    // embeds can't have more than 25 fields
+    for warning in active.iter().take(25) {
+        embed = embed.field(
+            EmbedFieldBuilder::new(
+                format!("{} {}", warning.kind.emoji(), warning.title),
+                format!(
+                    "[details]({}), issued <t:{}:R>",
+                    warning.link,
+                    warning.issued_at.timestamp()
+                ),
+            )
+            .build(),
+        );
+    }
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("subscribe")]
+#[description = "post new warnings for a state to a channel"]
+#[error_handler(handle_interaction_error)]
+async fn warnings_channel_subscribe(
+    ctx: &mut SlashContext<BotContext>,
+    #[description = "channel to post in"] channel: Id<ChannelMarker>,
+    #[autocomplete(autocomplete_state)]
+    #[description = "pick a state"]
+    state: String,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    let guild_id = require_manage_guild(ctx)?;
+    let state = parse_state(&state)?;
+
+    sqlx::query!(
+        r#"INSERT INTO warning_subscriptions (guild_id, channel_id, state)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (channel_id, state) DO NOTHING"#,
+        guild_id.get() as i64,
+        channel.get() as i64,
+        state.code()
+    )
+    .execute(ctx.data.bom.db())
+    .await?;
+
+    let embed = EmbedBuilder::new()
+        .title(format!(
+            "⚠️ New {} warnings will be posted in <#{channel}>",
+            state.code()
+        ))
+        .color(0x003366);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("unsubscribe")]
+#[description = "stop posting warnings for a state to a channel"]
+#[error_handler(handle_interaction_error)]
+async fn warnings_channel_unsubscribe(
+    ctx: &mut SlashContext<BotContext>,
+    #[description = "channel warnings are posted in"] channel: Id<ChannelMarker>,
+    #[autocomplete(autocomplete_state)]
+    #[description = "pick a state"]
+    state: String,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    let guild_id = require_manage_guild(ctx)?;
+    let state = parse_state(&state)?;
+
+    let deleted = sqlx::query!(
+        "DELETE FROM warning_subscriptions WHERE guild_id = ($1) AND channel_id = ($2) AND state = ($3)",
+        guild_id.get() as i64,
+        channel.get() as i64,
+        state.code()
+    )
+    .execute(ctx.data.bom.db())
+    .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(
+            anyhow::anyhow!("<#{channel}> isn't subscribed to {} warnings", state.code()).into(),
+        );
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(format!(
+            "⚠️ {} warnings will no longer be posted in <#{channel}>",
+            state.code()
+        ))
+        .color(0x003366);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+/// Name, observation product and station mapped to a radar location.
+async fn observation_station_for(
+    ctx: &BotContext,
+    bom_id: &str,
+) -> anyhow::Result<(String, String, i32)> {
+    let location = sqlx::query!(
+        "SELECT name, observation_product, observation_station FROM locations WHERE bom_radar_id = ($1)",
+        bom_id
+    )
+    .fetch_one(ctx.bom.db())
+    .await?;
+
+    let (Some(product), Some(station)) =
+        (location.observation_product, location.observation_station)
+    else {
+        anyhow::bail!("{} doesn't have an observation station", location.name);
+    };
+
+    Ok((location.name, product, station))
+}
+
+/// Latest reading from the station mapped to a radar location.
+async fn latest_observation_for(
+    ctx: &BotContext,
+    bom_id: &str,
+) -> anyhow::Result<(String, Observation)> {
+    let (name, product, station) = observation_station_for(ctx, bom_id).await?;
+    let observation = ctx.observations.get_latest(&product, station).await?;
+    Ok((name, observation))
+}
+
+/// Recorded history of a metric at the station mapped to a radar location.
+async fn observation_history_for(
+    ctx: &BotContext,
+    bom_id: &str,
+    metric: Metric,
+    period: chrono::TimeDelta,
+) -> anyhow::Result<(String, Vec<HistoryPoint>)> {
+    let (name, _, station) = observation_station_for(ctx, bom_id).await?;
+    let since = chrono::Utc::now() - period;
+    let points = history::load_history(ctx.bom.db(), station, metric, since).await?;
+    Ok((name, points))
+}
+
+#[command("history")]
+#[description = "chart recent observations"]
+#[error_handler(handle_interaction_error)]
+async fn history_command(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_location)]
+    #[description = "pick a location"]
+    location: Option<String>,
+    #[autocomplete(autocomplete_metric)]
+    #[description = "temp, rain or wind"]
+    metric: Option<String>,
+    #[description = "how far back, e.g. 24h, 7d or 2w"] period: Option<String>,
+) -> DefaultCommandResult {
+    ctx.defer(false).await?;
+
+    // perth
+    let location = location.unwrap_or_else(|| "IDR703".to_owned());
+    let metric = parse_metric(metric.as_deref())?;
+    let period = parse_period(period.as_deref())?;
+
+    let (location_name, points) =
+        observation_history_for(&ctx.data, &location, metric, period).await?;
+    let time_zone = ctx.data.bom.radar_time_zone(&location).await?;
+
+    let title = format!(
+        "{location_name} {} ({})",
+        metric.description(),
+        metric.unit()
+    );
+    let bytes = tokio::task::spawn_blocking(move || {
+        history::encode_png(&history::render_chart(&title, metric, &points, time_zone))
+    })
+    .await??;
+
+    let filename = "history.png".to_owned();
+    let mut embed = EmbedBuilder::new()
+        .title(format!("📈 {location_name} {}", metric.description()))
+        .color(0x003366);
+    match ImageSource::attachment(&filename) {
+        Ok(image) => embed = embed.image(image),
+        Err(e) => tracing::error!("error with image url: {e}"),
+    }
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .attachments(&[Attachment::from_bytes(filename, bytes, 1)])
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[description = "get the current conditions"]
+#[error_handler(handle_interaction_error)]
+async fn now(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_location)]
+    #[description = "pick a location"]
+    location: Option<String>,
+) -> DefaultCommandResult {
+    ctx.defer(false).await?;
+
+    // perth
+    let location = location.unwrap_or_else(|| "IDR703".to_owned());
+    let (location_name, observation) = latest_observation_for(&ctx.data, &location).await?;
+
+    let mut embed = EmbedBuilder::new()
+        .title(format!("🌡️ Now in {location_name}"))
+        .color(0x003366)
+        .footer(EmbedFooterBuilder::new(format!(
+            "{} ({})",
+            observation.name, observation.wmo
+        )));
+
+    if let Some(air_temp) = observation.air_temp {
+        let feels_like = observation
+            .apparent_t
+            .map_or_else(String::new, |t| format!(", feels like {t}°c"));
+        embed = embed.field(
+            EmbedFieldBuilder::new("Temperature", format!("{air_temp}°c{feels_like}")).inline(),
+        );
+    }
+
+    if let Some(rel_hum) = observation.rel_hum {
+        embed = embed.field(EmbedFieldBuilder::new("Humidity", format!("{rel_hum}%")).inline());
+    }
+
+    if let Some(wind_spd_kmh) = observation.wind_spd_kmh {
+        let direction = observation.wind_dir.as_deref().unwrap_or("");
+        let gusts = observation
+            .gust_kmh
+            .map_or_else(String::new, |g| format!(", gusts {g} km/h"));
+        embed = embed.field(
+            EmbedFieldBuilder::new("Wind", format!("{direction} {wind_spd_kmh} km/h{gusts}"))
+                .inline(),
+        );
+    }
+
+    if let Some(rain) = observation.rain_since_9am() {
+        embed =
+            embed.field(EmbedFieldBuilder::new("Rain since 9am", format!("{rain} mm")).inline());
+    }
+
+    if let Some(press) = observation.press {
+        embed = embed.field(EmbedFieldBuilder::new("Pressure", format!("{press} hPa")).inline());
+    }
+
+    if let Some(time) = observation.time() {
+        embed = embed.timestamp(
+            Timestamp::from_secs(time.timestamp())
+                .context("must have valid time")
+                .unwrap(),
+        );
+    }
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("add")]
+#[description = "add a forecast location"]
+#[error_handler(handle_interaction_error)]
+async fn location_add(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_willyweather_search)]
+    #[description = "search for a town or postcode"]
+    location: String,
+    #[autocomplete(autocomplete_forecast_provider)]
+    #[description = "where forecasts come from, defaults to willyweather"]
+    provider: Option<String>,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    require_operator(ctx)?;
+
+    let provider = match provider.as_deref() {
+        Some(provider) => Provider::from_name(provider)
+            .filter(|p| *p != Provider::Bom)
+            .with_context(|| format!("unknown forecast provider: {provider}"))?,
+        None => Provider::WillyWeather,
+    };
+
+    let willyweather = ctx
+        .data
+        .forecaster
+        .willyweather()
+        .context("willyweather isn't configured")?;
+    let location = willyweather.get_location(&location).await?;
+
+    let added = ForecastLocation::add(ctx.data.forecaster.db(), &location, provider).await?;
+    let title = if added {
+        format!(
+            "📍 Added {}, {} using {} forecasts",
+            location.name,
+            location.state,
+            provider.name()
+        )
+    } else {
+        format!("📍 {} has already been added", location.name)
+    };
+
+    let embed = EmbedBuilder::new().title(title).color(0x003366);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("add")]
+#[description = "add a radar or turn a disabled one back on"]
+#[error_handler(handle_interaction_error)]
+async fn radar_admin_add(
+    ctx: &mut SlashContext<BotContext>,
+    #[description = "bom product id, e.g. IDR703"] radar: String,
+    #[description = "name to show, defaults to the product id"] name: Option<String>,
+    #[description = "latitude of the radar"] latitude: Option<f64>,
+    #[description = "longitude of the radar"] longitude: Option<f64>,
+    #[autocomplete(autocomplete_state)]
+    #[description = "state the radar is in"]
+    state: Option<String>,
+    #[description = "background refresh priority, 0 only fetches on demand"] priority: Option<i64>,
+    #[description = "time zone for frame times, defaults to Australia/Perth"] time_zone: Option<
+        String,
+    >,
+    #[description = "ranges the site publishes in km, e.g. 128,256,512"] ranges: Option<String>,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    require_operator(ctx)?;
+
+    let (bom_radar_id, range_km) = parse_radar_product(&radar)?;
+    let state = state.as_deref().map(parse_state).transpose()?;
+    let time_zone = time_zone.as_deref().map(parse_time_zone).transpose()?;
+    let ranges = ranges
+        .as_deref()
+        .map(|ranges| parse_radar_ranges(ranges, &bom_radar_id, range_km))
+        .transpose()?;
+
+    // fails if bom doesn't have the products, before anything is saved
+    for range_km in ranges.as_deref().unwrap_or(&[range_km]) {
+        let product = radar::product_for_range(&bom_radar_id, *range_km)
+            .with_context(|| format!("there's no {range_km}km product for {bom_radar_id}"))?;
+        ctx.data
+            .bom
+            .generate_radar_background(&product)
+            .await
+            .with_context(|| format!("couldn't fetch the background for {product}"))?;
+    }
+
+    let location = sqlx::query!(
+        r#"INSERT INTO locations
+            (bom_radar_id, name, latitude, longitude, state, ranges, priority, time_zone, active)
+            VALUES ($1, COALESCE($2, $1), $3, $4, $5, COALESCE($6, ARRAY[$9]::INTEGER[]), COALESCE($7, 0), COALESCE($8, 'Australia/Perth'), TRUE)
+            ON CONFLICT (bom_radar_id) DO UPDATE SET
+                name = COALESCE($2, locations.name),
+                latitude = COALESCE($3, locations.latitude),
+                longitude = COALESCE($4, locations.longitude),
+                state = COALESCE($5, locations.state),
+                ranges = COALESCE($6, locations.ranges),
+                priority = COALESCE($7, locations.priority),
+                time_zone = COALESCE($8, locations.time_zone),
+                active = TRUE
+            RETURNING name, priority, ranges"#,
+        bom_radar_id,
+        name,
+        latitude,
+        longitude,
+        state.map(|s| s.code()),
+        ranges.as_deref(),
+        priority.map(|p| p as i32),
+        time_zone.map(|tz| tz.name()),
+        range_km
+    )
+    .fetch_one(ctx.data.bom.db())
+    .await?;
+
+    let embed = EmbedBuilder::new()
+        .title(format!("📡 {} ({bom_radar_id}) is active", location.name))
+        .description(format!(
+            "{} km ranges, priority {}",
+            format_ranges(&location.ranges),
+            location.priority
+        ))
+        .color(0x003366);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("disable")]
+#[description = "stop refreshing and offering a radar"]
+#[error_handler(handle_interaction_error)]
+async fn radar_admin_disable(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_location)]
+    #[description = "pick a radar"]
+    radar: String,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    require_operator(ctx)?;
+
+    let location = sqlx::query!(
+        "UPDATE locations SET active = FALSE WHERE bom_radar_id = ($1) RETURNING name",
+        radar
+    )
+    .fetch_optional(ctx.data.bom.db())
+    .await?
+    .with_context(|| format!("there's no radar {radar}"))?;
+
+    let embed = EmbedBuilder::new()
+        .title(format!("📡 {} ({radar}) is disabled", location.name))
+        .color(0x003366);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("list")]
+#[description = "list radars and their refresh priority"]
+#[error_handler(handle_interaction_error)]
+async fn radar_admin_list(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_state)]
+    #[description = "only show radars in a state"]
+    state: Option<String>,
+) -> DefaultCommandResult {
+    ctx.defer(true).await?;
+
+    let state = state.as_deref().map(parse_state).transpose()?;
+    let radars = sqlx::query!(
+        r#"SELECT bom_radar_id, name, state, ranges, priority, active FROM locations
+            WHERE ($1::TEXT IS NULL OR state = $1)
+            ORDER BY state, priority DESC, name"#,
+        state.map(|s| s.code())
+    )
+    .fetch_all(ctx.data.bom.db())
+    .await?;
+
+    let mut embed = EmbedBuilder::new().title("📡 Radars").color(0x003366);
+
+    if radars.is_empty() {
+        embed = embed.description("There are no radars");
+    }
+
+    for radars in radars.chunk_by(|a, b| a.state == b.state) {
+        let state = radars[0].state.as_deref().unwrap_or("Unknown");
+        let lines = radars
+            .iter()
+            .map(|radar| {
+                let mut line = format!(
+                    "`{}` {} · {} km",
+                    radar.bom_radar_id,
+                    radar.name,
+                    format_ranges(&radar.ranges)
+                );
+                if radar.priority > 0 {
+                    line.push_str(&format!(" · priority {}", radar.priority));
+                }
+
+                if radar.active {
+                    line
+                } else {
+                    format!("~~{line}~~ (disabled)")
+                }
+            })
+            .collect::<Vec<_>>();
+
+        embed = embed.field(EmbedFieldBuilder::new(state, lines.join("\n")).build());
+    }
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+/// Forecasts only WillyWeather has need the location's WillyWeather id.
+async fn willyweather_location_for(
+    ctx: &BotContext,
+    location: Option<String>,
+) -> anyhow::Result<(&WillyWeatherAPI, String)> {
+    let willyweather = ctx
+        .forecaster
+        .willyweather()
+        .context("willyweather isn't configured")?;
+
+    // perth
+    let location = location.unwrap_or_else(|| WillyWeatherAPI::PERTH_ID.to_owned());
+    let location = ForecastLocation::get(ctx.forecaster.db(), &location).await?;
+    let id = location
+        .willyweather_id
+        .with_context(|| format!("{} isn't a willyweather location", location.name))?;
+
+    Ok((willyweather, id))
+}
+
+#[command]
+#[description = "get sunrise and sunset times"]
+#[error_handler(handle_interaction_error)]
+async fn sun(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_location_forecast)]
+    #[description = "pick a location"]
+    location: Option<String>,
+    #[description = "number of days"] days: Option<i64>,
+) -> DefaultCommandResult {
+    ctx.defer(false).await?;
+
+    let (willyweather, id) = willyweather_location_for(&ctx.data, location).await?;
+    let forecast = willyweather
+        .get_sunrise_sunset(&id, &days.unwrap_or(7))
+        .await?;
+    let embed = almanac::sun_embed(forecast)?;
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[description = "get high and low tides"]
+#[error_handler(handle_interaction_error)]
+async fn tides(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_location_forecast)]
+    #[description = "pick a location"]
+    location: Option<String>,
+    #[description = "number of days"] days: Option<i64>,
+) -> DefaultCommandResult {
+    ctx.defer(false).await?;
+
+    let (willyweather, id) = willyweather_location_for(&ctx.data, location).await?;
+    let forecast = willyweather.get_tides(&id, &days.unwrap_or(7)).await?;
+    let embed = almanac::tides_embed(forecast)?;
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[description = "get moon phases"]
+#[error_handler(handle_interaction_error)]
+async fn moon(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_location_forecast)]
+    #[description = "pick a location"]
+    location: Option<String>,
+    #[description = "number of days"] days: Option<i64>,
+) -> DefaultCommandResult {
+    ctx.defer(false).await?;
+
+    let (willyweather, id) = willyweather_location_for(&ctx.data, location).await?;
+    let forecast = willyweather
+        .get_moon_phases(&id, &days.unwrap_or(7))
+        .await?;
+    let embed = almanac::moon_embed(forecast)?;
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command("marine")]
+#[description = "get swell and wind for fishing and surfing"]
+#[error_handler(handle_interaction_error)]
+async fn marine_command(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_location_forecast)]
+    #[description = "pick a location"]
+    location: Option<String>,
+    #[description = "number of days"] days: Option<i64>,
+) -> DefaultCommandResult {
+    ctx.defer(false).await?;
+
+    let (willyweather, id) = willyweather_location_for(&ctx.data, location).await?;
+    let forecast = willyweather.get_marine(&id, &days.unwrap_or(2)).await?;
+    let embed = marine::marine_embed(&marine::marine_forecast(forecast)?);
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[description = "get forecast information from bom"]
+#[error_handler(handle_interaction_error)]
+async fn forecast(
+    ctx: &mut SlashContext<BotContext>,
+    #[autocomplete(autocomplete_location_forecast)]
+    #[description = "pick a location"]
+    location: Option<String>,
+    #[description = "number of days"] days: Option<i64>,
+    #[description = "show the next hours instead of days"] hourly: Option<bool>,
+    #[description = "number of hours for hourly forecasts, up to 48"] hours: Option<i64>,
+) -> DefaultCommandResult {
+    ctx.defer(false).await?;
+
+    // perth
+    let location = location.unwrap_or_else(|| WillyWeatherAPI::PERTH_ID.to_owned());
+
+    let embed = if hourly.unwrap_or(false) {
+        let hours = hourly_forecast_hours(hours);
+        let forecast = ctx
+            .data
+            .forecaster
+            .get_hourly_forecast(&location, hours)
+            .await?;
+        hourly_forecast_embed(&forecast)
+    } else {
+        let days = days.unwrap_or(7);
+        let forecast = ctx.data.forecaster.get_forecast(&location, days).await?;
+        forecast_embed(&forecast)
+    };
+
+    ctx.interaction_client
+        .update_response(&ctx.interaction.token)
+        .embeds(Some(&[embed.build()]))
+        .await?;
+
+    Ok(())
+}
+
+async fn health(_ctx: State<BotContext>) -> StatusCode {
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Deserialize)]
+struct ForecastParams {
+    location: Option<String>,
+    hourly: Option<bool>,
+    hours: Option<i64>,
+}
+
+async fn forecast_endpoint(
+    ctx: State<BotContext>,
     params: Query<ForecastParams>,
 ) -> Result<Json<ForecastEndpointResponse>, AppError> {
     let location = params
@@ -425,42 +1904,293 @@ async fn forecast_endpoint(
         .clone()
         .unwrap_or_else(|| WillyWeatherAPI::PERTH_ID.to_owned());
 
-    let forecast_resp = ctx.willyweather.get_forecast(&location, &7).await?;
-    let mut forecast_for_days = Vec::with_capacity(7);
-
-    for (i, days) in forecast_resp.forecasts.weather.days.into_iter().enumerate() {
-        let entry = days
-            .entries
-            .into_iter()
-            .next()
-            .context("must have entries")?;
-        let min = entry.min;
-        let max = entry.max;
-        let description = entry.precis;
-        let datetime_with_timezone = &format!("{} +0800", entry.date_time);
-        let datetime = DateTime::parse_from_str(datetime_with_timezone, "%Y-%m-%d %H:%M:%S %z")?;
-        let emoji = PRECIS_TO_EMOJI
-            .get(&entry.precis_code)
-            .map_or("", |e| e)
-            .to_string();
-        let uv_level = forecast_resp.forecasts.uv.days.get(i).map(|e| &e.alert);
-
-        forecast_for_days.push(ForecastForDay {
-            date_time: datetime.to_rfc3339(),
-            code: entry.precis_code,
-            emoji,
-            description,
-            min,
-            max,
-            uv: uv_level.map(|uv| uv.max_index),
-        });
+    if params.hourly.unwrap_or(false) {
+        let hours = hourly_forecast_hours(params.hours);
+        let forecast = ctx.forecaster.get_hourly_forecast(&location, hours).await?;
+
+        return Ok(Json(ForecastEndpointResponse {
+            days: Vec::new(),
+            hours: forecast.endpoint_hours(),
+        }));
     }
 
+    let forecast = ctx.forecaster.get_forecast(&location, 7).await?;
+
     Ok(Json(ForecastEndpointResponse {
-        days: forecast_for_days,
+        days: forecast.endpoint_days(),
+        hours: Vec::new(),
+    }))
+}
+
+#[derive(Deserialize)]
+struct MarineParams {
+    location: Option<String>,
+    days: Option<i64>,
+}
+
+async fn marine_endpoint(
+    ctx: State<BotContext>,
+    params: Query<MarineParams>,
+) -> Result<Json<MarineEndpointResponse>, AppError> {
+    let (willyweather, id) = willyweather_location_for(&ctx, params.location.clone()).await?;
+    let forecast = willyweather
+        .get_marine(&id, &params.days.unwrap_or(2))
+        .await?;
+    let forecast = marine::marine_forecast(forecast)?;
+
+    Ok(Json(MarineEndpointResponse {
+        location: forecast.location,
+        blocks: forecast
+            .blocks
+            .iter()
+            .map(marine::MarineBlock::endpoint_block)
+            .collect(),
+    }))
+}
+
+#[derive(Deserialize)]
+struct LoopParams {
+    location: Option<String>,
+    format: Option<OutputFormat>,
+    frames: Option<i64>,
+    delay: Option<i64>,
+    hold: Option<i64>,
+    loops: Option<i64>,
+    /// Only used for radar loops.
+    nowcast: Option<bool>,
+    /// Only used for radar and timelapse loops.
+    range: Option<i64>,
+}
+
+impl LoopParams {
+    fn options(&self, defaults: RenderOptions) -> RenderOptions {
+        defaults.with_overrides(self.frames, self.delay, self.hold, self.loops)
+    }
+}
+
+fn loop_response(format: OutputFormat, bytes: Vec<u8>) -> Response {
+    ([(header::CONTENT_TYPE, format.content_type())], bytes).into_response()
+}
+
+async fn radar_endpoint(
+    ctx: State<BotContext>,
+    params: Query<LoopParams>,
+) -> Result<Response, AppError> {
+    let location = params.location.as_deref().unwrap_or("IDR703");
+    let (_, location) = resolve_radar(&ctx, location).await?;
+    let location = radar_product_for_range(&ctx, location, params.range).await?;
+    let format = params.format.unwrap_or_default();
+    let options = params.options(RenderOptions::RADAR);
+
+    let (_, bytes) = ctx
+        .bom
+        .generate_radar_gif_for(&location, &options, format, params.nowcast.unwrap_or(false))
+        .await?;
+
+    Ok(loop_response(format, bytes))
+}
+
+async fn timelapse_endpoint(
+    ctx: State<BotContext>,
+    params: Query<LoopParams>,
+) -> Result<Response, AppError> {
+    let location = params
+        .location
+        .clone()
+        .unwrap_or_else(|| "IDR703".to_owned());
+    let location = radar_product_for_range(&ctx, location, params.range).await?;
+    let format = params.format.unwrap_or_default();
+    let options = params.options(RenderOptions::TIMELAPSE);
+
+    let (_, bytes) = ctx
+        .bom
+        .get_radar_timelapse_24hr_for(&location, &options, format)
+        .await?;
+
+    Ok(loop_response(format, bytes))
+}
+
+async fn satellite_endpoint(
+    ctx: State<BotContext>,
+    params: Query<LoopParams>,
+) -> Result<Response, AppError> {
+    let location = params.location.as_deref().unwrap_or("IDE00416");
+    let format = params.format.unwrap_or_default();
+    let options = params.options(RenderOptions::SATELLITE);
+
+    let (_, bytes) = ctx
+        .bom
+        .get_latest_satellite_gif_for(location, &options, format)
+        .await?;
+
+    Ok(loop_response(format, bytes))
+}
+
+#[derive(Deserialize)]
+struct RainfallParams {
+    location: Option<String>,
+    x: Option<u32>,
+    y: Option<u32>,
+    /// Lowest rate in mm/h that counts towards coverage.
+    threshold: Option<f32>,
+}
+
+async fn rainfall_endpoint(
+    ctx: State<BotContext>,
+    params: Query<RainfallParams>,
+) -> Result<Json<RainfallEndpointResponse>, AppError> {
+    let location = params.location.as_deref().unwrap_or("IDR703");
+    let (time, grid) = ctx.bom.get_latest_rainfall_for(location).await?;
+
+    let rate = match (params.x, params.y) {
+        (Some(x), Some(y)) => grid.rate_at(x, y),
+        _ => None,
+    };
+
+    Ok(Json(RainfallEndpointResponse {
+        date_time: time.to_rfc3339(),
+        max_rate: grid.max_rate(),
+        coverage: grid.coverage(params.threshold.unwrap_or(rainfall::MIN_RAIN_RATE)),
+        rate,
+    }))
+}
+
+#[derive(Deserialize)]
+struct RainParams {
+    lat: f64,
+    lng: f64,
+}
+
+async fn rain_endpoint(
+    ctx: State<BotContext>,
+    params: Query<RainParams>,
+) -> Result<Json<RainEndpointResponse>, AppError> {
+    let rain = ctx.bom.get_rain_at(params.lat, params.lng).await?;
+    let (time, rate) = rain.current().context("must have a frame")?;
+
+    Ok(Json(RainEndpointResponse {
+        radar: rain.site.name.clone(),
+        bom_radar_id: rain.site.bom_radar_id.clone(),
+        date_time: time.to_rfc3339(),
+        rate,
+        intensity: rainfall::intensity(rate).to_owned(),
+        trend: rain.trend().name().to_owned(),
+        expected_in_minutes: rain.expected_in_minutes,
+        history: rain
+            .samples
+            .iter()
+            .map(|(time, rate)| RainSample {
+                date_time: time.to_rfc3339(),
+                rate: *rate,
+            })
+            .collect(),
+    }))
+}
+
+#[derive(Deserialize)]
+struct WarningsParams {
+    state: Option<String>,
+}
+
+async fn warnings_endpoint(
+    ctx: State<BotContext>,
+    params: Query<WarningsParams>,
+) -> Result<Json<WarningsEndpointResponse>, AppError> {
+    let state = params.state.as_deref().map(parse_state).transpose()?;
+    let warnings = warnings::active_warnings(ctx.bom.db(), state).await?;
+
+    Ok(Json(WarningsEndpointResponse { warnings }))
+}
+
+#[derive(Deserialize)]
+struct ObservationsParams {
+    location: Option<String>,
+}
+
+async fn observations_endpoint(
+    ctx: State<BotContext>,
+    params: Query<ObservationsParams>,
+) -> Result<Json<ObservationsEndpointResponse>, AppError> {
+    let location = params.location.as_deref().unwrap_or("IDR703");
+    let (location, observation) = latest_observation_for(&ctx, location).await?;
+
+    Ok(Json(ObservationsEndpointResponse {
+        location,
+        observation,
     }))
 }
 
+#[derive(Deserialize)]
+struct HistoryParams {
+    location: Option<String>,
+    metric: Option<String>,
+    period: Option<String>,
+    /// `json` (default) or `csv`.
+    format: Option<String>,
+}
+
+async fn history_endpoint(
+    ctx: State<BotContext>,
+    params: Query<HistoryParams>,
+) -> Result<Response, AppError> {
+    let location = params.location.as_deref().unwrap_or("IDR703");
+    let metric = parse_metric(params.metric.as_deref())?;
+    let period = parse_period(params.period.as_deref())?;
+    let (location, points) = observation_history_for(&ctx, location, metric, period).await?;
+
+    match params.format.as_deref() {
+        Some("csv") => Ok((
+            [(header::CONTENT_TYPE, "text/csv")],
+            history::to_csv(metric, &points),
+        )
+            .into_response()),
+        Some("json") | None => Ok(Json(HistoryEndpointResponse {
+            location,
+            metric,
+            unit: metric.unit().to_owned(),
+            points,
+        })
+        .into_response()),
+        Some(format) => Err(anyhow::anyhow!("unknown format: {format}").into()),
+    }
+}
+
+fn object_store_from_env() -> anyhow::Result<Box<dyn ObjectStore>> {
+    match std::env::var("STORAGE_BACKEND").as_deref() {
+        Ok("local") => {
+            let path = std::env::var("STORAGE_PATH")?;
+            Ok(Box::new(LocalObjectStore::new(path)))
+        }
+        Ok("memory") => Ok(Box::new(MemoryObjectStore::new())),
+        _ => {
+            let access_key_id = std::env::var("BUCKET_ACCESS_KEY_ID")?;
+            let access_secret_key = std::env::var("BUCKET_ACCESS_SECRET_KEY")?;
+            let bucket_name = std::env::var("BUCKET_NAME")?;
+            let bucket_endpoint = std::env::var("BUCKET_ENDPOINT")?;
+
+            let credentials = s3::creds::Credentials::new(
+                Some(&access_key_id),
+                Some(&access_secret_key),
+                None,
+                None,
+                None,
+            )?;
+
+            let bucket = s3::Bucket::new(
+                &bucket_name,
+                s3::Region::Custom {
+                    region: "".to_owned(),
+                    endpoint: bucket_endpoint,
+                },
+                credentials,
+            )?
+            .with_path_style();
+
+            Ok(Box::new(S3ObjectStore::new(bucket)))
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::registry()
@@ -471,29 +2201,26 @@ async fn main() -> anyhow::Result<()> {
     let database_url = std::env::var("DATABASE_URL")?;
     let token = std::env::var("DISCORD_TOKEN")?;
 
-    let access_key_id = std::env::var("BUCKET_ACCESS_KEY_ID")?;
-    let access_secret_key = std::env::var("BUCKET_ACCESS_SECRET_KEY")?;
-    let bucket_name = std::env::var("BUCKET_NAME")?;
-    let bucket_endpoint = std::env::var("BUCKET_ENDPOINT")?;
-    let willyweather_api_key = std::env::var("WILLYWEATHER_API_KEY")?;
-
-    let credentials = s3::creds::Credentials::new(
-        Some(&access_key_id),
-        Some(&access_secret_key),
-        None,
-        None,
-        None,
-    )?;
-
-    let bucket = s3::Bucket::new(
-        &bucket_name,
-        s3::Region::Custom {
-            region: "".to_owned(),
-            endpoint: bucket_endpoint,
-        },
-        credentials,
-    )?
-    .with_path_style();
+    let willyweather_api_key = std::env::var("WILLYWEATHER_API_KEY").ok();
+    let forecast_provider = std::env::var("FORECAST_PROVIDER")
+        .ok()
+        .map(|provider| {
+            Provider::from_name(&provider)
+                .with_context(|| format!("unknown forecast provider: {provider}"))
+        })
+        .transpose()?;
+    let image_source_url = std::env::var("IMAGE_SOURCE_URL").ok();
+    let operators = std::env::var("OPERATOR_USER_IDS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse()
+                .with_context(|| format!("invalid operator user id: {id}"))
+        })
+        .collect::<anyhow::Result<Vec<Id<UserMarker>>>>()?;
+    let bucket = object_store_from_env()?;
 
     let pool = PgPoolOptions::new()
         .max_connections(5)
@@ -502,14 +2229,26 @@ async fn main() -> anyhow::Result<()> {
 
     sqlx::migrate!("./migrations").run(&pool).await?;
 
-    let willyweather = WillyWeatherAPI::new(willyweather_api_key);
-    let bom = Arc::new(bom::BOM::new(bucket, pool).await?);
+    let image_source = match image_source_url {
+        Some(url) => source::from_url(&url),
+        None => Box::new(source::FtpImageSource::bom()),
+    };
+
+    let bom = Arc::new(bom::BOM::new(image_source, bucket, pool).await?);
     bom.generate_radar_backgrounds().await?;
 
+    let forecaster = Forecaster::new(
+        bom.clone(),
+        willyweather_api_key.map(WillyWeatherAPI::new),
+        forecast_provider,
+    );
+
     let context = BotContext(
         BotContextInner {
             bom: bom.clone(),
-            willyweather,
+            forecaster,
+            observations: ObservationsAPI::new(),
+            operators,
         }
         .into(),
     );
@@ -531,6 +2270,15 @@ async fn main() -> anyhow::Result<()> {
     let app = axum::Router::new()
         .route("/health", get(health))
         .route("/forecast", get(forecast_endpoint))
+        .route("/radar", get(radar_endpoint))
+        .route("/timelapse", get(timelapse_endpoint))
+        .route("/satellite", get(satellite_endpoint))
+        .route("/rainfall", get(rainfall_endpoint))
+        .route("/rain", get(rain_endpoint))
+        .route("/warnings", get(warnings_endpoint))
+        .route("/observations", get(observations_endpoint))
+        .route("/history", get(history_endpoint))
+        .route("/marine", get(marine_endpoint))
         .with_state(context.clone());
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8000").await.unwrap();
@@ -541,6 +2289,8 @@ async fn main() -> anyhow::Result<()> {
     if !cfg!(debug_assertions) {
         tracing::info!("spawning background thread");
         let bom_cloned = bom.clone();
+        let http_cloned = http.clone();
+        let context_cloned = context.clone();
         tokio::spawn(async move {
             loop {
                 let bom_cloned = bom_cloned.clone();
@@ -552,9 +2302,58 @@ async fn main() -> anyhow::Result<()> {
                     tracing::info!("error in cleanup: {e}");
                 }
 
+                if let Err(e) =
+                    alerts::send_rain_alerts(bom_cloned.clone(), http_cloned.clone()).await
+                {
+                    tracing::info!("error in rain alerts: {e}");
+                }
+
+                if let Err(e) =
+                    history::record_observations(bom_cloned.db(), &context_cloned.observations)
+                        .await
+                {
+                    tracing::info!("error recording observations: {e}");
+                }
+
                 tokio::time::sleep(Duration::from_secs(900)).await;
             }
         });
+
+        tracing::info!("spawning warnings poller");
+        let bom_cloned = bom.clone();
+        let http_cloned = http.clone();
+        tokio::spawn(async move {
+            loop {
+                let new_warnings =
+                    warnings::refresh_warnings(bom_cloned.source(), bom_cloned.db()).await;
+                if let Err(e) =
+                    warnings::post_new_warnings(bom_cloned.db(), &http_cloned, &new_warnings).await
+                {
+                    tracing::info!("error posting warnings: {e}");
+                }
+
+                tokio::time::sleep(Duration::from_secs(300)).await;
+            }
+        });
+
+        tracing::info!("spawning forecast scheduler");
+        let context_cloned = context.clone();
+        let http_cloned = http.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = schedule::post_due_forecasts(
+                    &context_cloned.bom,
+                    &context_cloned.forecaster,
+                    &http_cloned,
+                )
+                .await
+                {
+                    tracing::info!("error in forecast schedules: {e}");
+                }
+
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+        });
     }
 
     let app_id = http.current_user_application().await?.model().await?.id;
@@ -562,9 +2361,51 @@ async fn main() -> anyhow::Result<()> {
     let framework = Arc::new(
         Framework::builder(Arc::clone(&http), app_id, context)
             .command(radar)
+            .command(radar_mosaic)
             .command(satellite)
             .command(timelapse)
             .command(forecast)
+            .command(rain_now)
+            .command(warnings_command)
+            .command(now)
+            .command(history_command)
+            .command(sun)
+            .command(tides)
+            .command(moon)
+            .command(marine_command)
+            .group(|g| {
+                g.name("alerts")
+                    .description("rain alerts sent by dm")
+                    .command(alerts_subscribe)
+                    .command(alerts_list)
+                    .command(alerts_unsubscribe)
+            })
+            .group(|g| {
+                g.name("warnings-channel")
+                    .description("post new bom warnings to a channel")
+                    .command(warnings_channel_subscribe)
+                    .command(warnings_channel_unsubscribe)
+            })
+            .group(|g| {
+                g.name("location")
+                    .description("forecast locations")
+                    .command(location_add)
+            })
+            .group(|g| {
+                g.name("radar-admin")
+                    .description("manage the radars the bot knows about")
+                    .command(radar_admin_add)
+                    .command(radar_admin_disable)
+                    .command(radar_admin_list)
+            })
+            .group(|g| {
+                g.name("schedule")
+                    .description("daily forecast posts")
+                    .command(schedule_forecast)
+                    .command(schedule_edit)
+                    .command(schedule_delete)
+                    .command(schedule_list)
+            })
             .build(),
     );
 
@@ -0,0 +1,122 @@
+use chrono::{DateTime, FixedOffset, TimeDelta, Timelike, Utc};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+
+use crate::{
+    forecast::{format_day, parse_time_zone, ForecastError, Provider},
+    types::{MarineForBlock, WillyWeatherForecast},
+    willyweather::graph_entries,
+};
+
+/// Conditions are shown every few hours, the graphs are hourly.
+const BLOCK_HOURS: u32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct MarineBlock {
+    pub date_time: DateTime<FixedOffset>,
+    pub swell_height: Option<f64>,
+    pub swell_period: Option<f64>,
+    pub swell_direction: Option<String>,
+    pub wind_speed: Option<f64>,
+    pub wind_direction: Option<String>,
+}
+
+impl MarineBlock {
+    pub fn endpoint_block(&self) -> MarineForBlock {
+        MarineForBlock {
+            date_time: self.date_time.to_rfc3339(),
+            swell_height: self.swell_height,
+            swell_period: self.swell_period,
+            swell_direction: self.swell_direction.clone(),
+            wind_speed: self.wind_speed,
+            wind_direction: self.wind_direction.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MarineForecast {
+    pub location: String,
+    pub blocks: Vec<MarineBlock>,
+}
+
+/// Pairs swell and wind for each block from the current one onwards.
+pub fn marine_forecast(forecast: WillyWeatherForecast) -> Result<MarineForecast, ForecastError> {
+    let time_zone = parse_time_zone(&forecast.location.time_zone)?;
+    let swells = graph_entries(forecast.forecasts.swell, time_zone, |e| &e.date_time)?;
+    let winds = graph_entries(forecast.forecasts.wind, time_zone, |e| &e.date_time)?;
+
+    // swell is missing inland, wind is always there
+    let times = if swells.is_empty() {
+        winds.iter().map(|(time, _)| *time).collect::<Vec<_>>()
+    } else {
+        swells.iter().map(|(time, _)| *time).collect()
+    };
+
+    let current_block = Utc::now() - TimeDelta::hours(BLOCK_HOURS as i64);
+    let blocks = times
+        .into_iter()
+        .filter(|time| *time > current_block && time.hour() % BLOCK_HOURS == 0)
+        .map(|date_time| {
+            let swell = swells.iter().find(|(time, _)| *time == date_time);
+            let wind = winds.iter().find(|(time, _)| *time == date_time);
+
+            MarineBlock {
+                date_time,
+                swell_height: swell.map(|(_, swell)| swell.height),
+                swell_period: swell.map(|(_, swell)| swell.period),
+                swell_direction: swell.map(|(_, swell)| swell.direction_text.clone()),
+                wind_speed: wind.map(|(_, wind)| wind.speed),
+                wind_direction: wind.map(|(_, wind)| wind.direction_text.clone()),
+            }
+        })
+        .collect();
+
+    Ok(MarineForecast {
+        location: forecast.location.name,
+        blocks,
+    })
+}
+
+pub fn marine_embed(forecast: &MarineForecast) -> EmbedBuilder {
+    let mut embed = EmbedBuilder::new()
+        .title(format!("🌊 Marine forecast for {}", forecast.location))
+        .color(0x003366)
+        .footer(EmbedFooterBuilder::new(Provider::WillyWeather.footer()));
+
+    if forecast.blocks.is_empty() {
+        return embed.description("There's no marine forecast for here");
+    }
+
+    // a field per day, discord only allows 25
+    for day in forecast
+        .blocks
+        .chunk_by(|a, b| a.date_time.date_naive() == b.date_time.date_naive())
+        .take(25)
+    {
+        let lines = day
+            .iter()
+            .map(|block| {
+                let mut details = Vec::new();
+                if let (Some(height), Some(period)) = (block.swell_height, block.swell_period) {
+                    let direction = block.swell_direction.as_deref().unwrap_or("");
+                    details.push(format!("🌊 {height:.1} m {period:.0}s {direction}"));
+                }
+                if let Some(speed) = block.wind_speed {
+                    let direction = block.wind_direction.as_deref().unwrap_or("");
+                    details.push(format!("💨 {direction} {speed:.0} km/h"));
+                }
+
+                format!(
+                    "`{}` {}",
+                    block.date_time.format("%H:%M"),
+                    details.join(" · ")
+                )
+            })
+            .collect::<Vec<_>>();
+
+        embed = embed
+            .field(EmbedFieldBuilder::new(format_day(&day[0].date_time), lines.join("\n")).build());
+    }
+
+    embed
+}
@@ -0,0 +1,104 @@
+use image::DynamicImage;
+use sqlx::PgPool;
+
+use crate::bom::BOMError;
+
+/// A composite of many radars for a region.
+#[derive(Debug, Clone)]
+pub struct Mosaic {
+    pub region: String,
+    pub name: String,
+    pub time_zone: String,
+    pub source: MosaicSource,
+}
+
+#[derive(Debug, Clone)]
+pub enum MosaicSource {
+    /// A BOM mosaic product as is or a region cropped out of one.
+    Product {
+        bom_radar_id: String,
+        /// Transparencies merged over the legend, mosaics don't have the
+        /// range rings and topography single sites do.
+        layers: Vec<String>,
+        crop: Option<Crop>,
+    },
+    /// Neighbouring sites stitched together, see [`crate::composite`].
+    Stitched { sites: Vec<String> },
+}
+
+/// Fractions of the mosaic frame so it holds if BOM changes the frame size.
+#[derive(Debug, Clone, Copy)]
+pub struct Crop {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl Crop {
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        let (width, height) = (img.width() as f64, img.height() as f64);
+        let (x, y) = ((self.left * width) as u32, (self.top * height) as u32);
+        let (right, bottom) = (
+            (self.right * width).ceil() as u32,
+            (self.bottom * height).ceil() as u32,
+        );
+
+        img.crop_imm(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+    }
+}
+
+impl Mosaic {
+    pub async fn get(db: &PgPool, region: &str) -> Result<Self, BOMError> {
+        let row = sqlx::query!(
+            "SELECT * FROM radar_mosaics WHERE region = UPPER($1)",
+            region
+        )
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| BOMError::UnknownMosaic(region.to_owned()))?;
+
+        let crop = match (row.crop_left, row.crop_top, row.crop_right, row.crop_bottom) {
+            (Some(left), Some(top), Some(right), Some(bottom)) => Some(Crop {
+                left,
+                top,
+                right,
+                bottom,
+            }),
+            _ => None,
+        };
+
+        let source = match row.bom_radar_id {
+            Some(bom_radar_id) if row.sites.is_empty() => MosaicSource::Product {
+                bom_radar_id,
+                layers: row.layers,
+                crop,
+            },
+            _ => MosaicSource::Stitched { sites: row.sites },
+        };
+
+        Ok(Self {
+            region: row.region,
+            name: row.name,
+            time_zone: row.time_zone,
+            source,
+        })
+    }
+
+    /// Every region and its name, for autocomplete.
+    pub async fn regions(db: &PgPool) -> Result<Vec<(String, String)>, BOMError> {
+        let regions = sqlx::query!("SELECT region, name FROM radar_mosaics ORDER BY region")
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .map(|row| (row.region, row.name))
+            .collect();
+
+        Ok(regions)
+    }
+
+    /// Backgrounds are per region as regions can share a product.
+    pub fn background_path(&self) -> String {
+        format!("{}.mosaic.base.png", self.region)
+    }
+}
@@ -0,0 +1,266 @@
+use image::{imageops, DynamicImage, GenericImageView, RgbImage};
+use openh264::{
+    encoder::{Encoder, EncoderConfig},
+    formats::{RgbSliceU8, YUVBuffer},
+    OpenH264API,
+};
+
+/// All sample times in the file are in milliseconds.
+const TIMESCALE: u32 = 1000;
+const BITRATE_BPS: u32 = 2_000_000;
+
+const NAL_IDR: u8 = 5;
+const NAL_SPS: u8 = 7;
+const NAL_PPS: u8 = 8;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Mp4Error {
+    #[error("a h264 encoding error occurred: {0}")]
+    H264(#[from] openh264::Error),
+
+    #[error("the encoder didn't emit a usable sps and pps")]
+    MissingParameterSets,
+}
+
+struct Sample {
+    data: Vec<u8>,
+    duration: u32,
+    is_sync: bool,
+}
+
+/// Encodes the frames as H.264 and wraps them in a minimal mp4 container.
+/// Every frame is shown for the matching entry in `durations_ms`.
+pub fn encode_h264(images: Vec<DynamicImage>, durations_ms: &[u32]) -> Result<Vec<u8>, Mp4Error> {
+    let (width, height) = images.first().map(|i| i.dimensions()).unwrap_or((2, 2));
+    // yuv 4:2:0 needs even dimensions
+    let (width, height) = (width + width % 2, height + height % 2);
+
+    let config = EncoderConfig::new()
+        .set_bitrate_bps(BITRATE_BPS)
+        .enable_skip_frame(false);
+    let mut encoder = Encoder::with_api_config(OpenH264API::from_source(), config)?;
+
+    let mut sps = Vec::new();
+    let mut pps = Vec::new();
+    let mut samples: Vec<Sample> = Vec::with_capacity(images.len());
+
+    for (img, duration) in images.into_iter().zip(durations_ms) {
+        let mut canvas = RgbImage::new(width, height);
+        imageops::overlay(&mut canvas, &img.to_rgb8(), 0, 0);
+
+        let yuv = YUVBuffer::from_rgb_source(RgbSliceU8::new(
+            canvas.as_raw(),
+            (width as usize, height as usize),
+        ));
+        let bitstream = encoder.encode(&yuv)?.to_vec();
+
+        let mut sample = Sample {
+            data: Vec::with_capacity(bitstream.len()),
+            duration: *duration,
+            is_sync: false,
+        };
+
+        for nal in split_annex_b(&bitstream) {
+            match nal[0] & 0x1f {
+                NAL_SPS => sps = nal.to_vec(),
+                NAL_PPS => pps = nal.to_vec(),
+                kind => {
+                    sample.is_sync |= kind == NAL_IDR;
+                    sample
+                        .data
+                        .extend_from_slice(&(nal.len() as u32).to_be_bytes());
+                    sample.data.extend_from_slice(nal);
+                }
+            }
+        }
+
+        match samples.last_mut() {
+            // the encoder skipped this frame, keep showing the previous one
+            Some(previous) if sample.data.is_empty() => previous.duration += sample.duration,
+            _ if sample.data.is_empty() => {}
+            _ => samples.push(sample),
+        }
+    }
+
+    // avcC copies the profile and level out of the sps header
+    if sps.len() < 4 || pps.is_empty() {
+        return Err(Mp4Error::MissingParameterSets);
+    }
+
+    Ok(mux(width, height, &sps, &pps, &samples))
+}
+
+fn split_annex_b(bitstream: &[u8]) -> Vec<&[u8]> {
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i + 3 <= bitstream.len() {
+        if bitstream[i..i + 3] == [0, 0, 1] {
+            starts.push((i, i + 3));
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &(_, data_start))| {
+            let end = starts
+                .get(n + 1)
+                .map_or(bitstream.len(), |&(next_start, _)| next_start);
+            // a 4 byte start code leaves a zero behind on the previous unit
+            let mut nal = &bitstream[data_start..end];
+            while let [rest @ .., 0] = nal {
+                nal = rest;
+            }
+            nal
+        })
+        .filter(|nal| !nal.is_empty())
+        .collect()
+}
+
+fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 8);
+    out.extend_from_slice(&(payload.len() as u32 + 8).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(payload);
+    out
+}
+
+fn full_box(kind: &[u8; 4], version: u8, flags: u32, payload: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(payload.len() + 4);
+    body.extend_from_slice(&(((version as u32) << 24) | flags).to_be_bytes());
+    body.extend_from_slice(payload);
+    mp4_box(kind, &body)
+}
+
+fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+fn mux(width: u32, height: u32, sps: &[u8], pps: &[u8], samples: &[Sample]) -> Vec<u8> {
+    let ftyp = mp4_box(b"ftyp", b"isom\0\0\x02\0isomiso2avc1mp41");
+    let data_size: usize = samples.iter().map(|s| s.data.len()).sum();
+
+    // moov sits before mdat so players can start straight away, its size
+    // doesn't depend on the chunk offset so build it once to measure it
+    let moov_size = moov(width, height, sps, pps, samples, 0).len();
+    let chunk_offset = (ftyp.len() + moov_size + 8) as u32;
+    let moov = moov(width, height, sps, pps, samples, chunk_offset);
+
+    let mut out = Vec::with_capacity(chunk_offset as usize + data_size);
+    out.extend_from_slice(&ftyp);
+    out.extend_from_slice(&moov);
+    out.extend_from_slice(&(data_size as u32 + 8).to_be_bytes());
+    out.extend_from_slice(b"mdat");
+    for sample in samples {
+        out.extend_from_slice(&sample.data);
+    }
+
+    out
+}
+
+fn moov(
+    width: u32,
+    height: u32,
+    sps: &[u8],
+    pps: &[u8],
+    samples: &[Sample],
+    chunk_offset: u32,
+) -> Vec<u8> {
+    let duration: u32 = samples.iter().map(|s| s.duration).sum();
+    let identity_matrix = u32s(&[0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x40000000]);
+
+    let mut mvhd = u32s(&[0, 0, TIMESCALE, duration, 0x10000]);
+    mvhd.extend_from_slice(&[0x01, 0x00, 0, 0]);
+    mvhd.extend_from_slice(&[0; 8]);
+    mvhd.extend_from_slice(&identity_matrix);
+    mvhd.extend_from_slice(&[0; 24]);
+    mvhd.extend_from_slice(&2u32.to_be_bytes());
+    let mvhd = full_box(b"mvhd", 0, 0, &mvhd);
+
+    let mut tkhd = u32s(&[0, 0, 1, 0, duration, 0, 0, 0, 0]);
+    tkhd.extend_from_slice(&identity_matrix);
+    tkhd.extend_from_slice(&u32s(&[width << 16, height << 16]));
+    let tkhd = full_box(b"tkhd", 0, 0x3, &tkhd);
+
+    let mut mdhd = u32s(&[0, 0, TIMESCALE, duration]);
+    // language "und"
+    mdhd.extend_from_slice(&[0x55, 0xc4, 0, 0]);
+    let mdhd = full_box(b"mdhd", 0, 0, &mdhd);
+
+    let mut hdlr = u32s(&[0]);
+    hdlr.extend_from_slice(b"vide");
+    hdlr.extend_from_slice(&[0; 12]);
+    hdlr.extend_from_slice(b"VideoHandler\0");
+    let hdlr = full_box(b"hdlr", 0, 0, &hdlr);
+
+    let vmhd = full_box(b"vmhd", 0, 1, &[0; 8]);
+    let dref = full_box(
+        b"dref",
+        0,
+        0,
+        &[&1u32.to_be_bytes()[..], &full_box(b"url ", 0, 1, &[])].concat(),
+    );
+    let dinf = mp4_box(b"dinf", &dref);
+
+    let mut avcc = vec![1, sps[1], sps[2], sps[3], 0xff, 0xe1];
+    avcc.extend_from_slice(&(sps.len() as u16).to_be_bytes());
+    avcc.extend_from_slice(sps);
+    avcc.push(1);
+    avcc.extend_from_slice(&(pps.len() as u16).to_be_bytes());
+    avcc.extend_from_slice(pps);
+
+    let mut avc1 = vec![0; 6];
+    avc1.extend_from_slice(&1u16.to_be_bytes());
+    avc1.extend_from_slice(&[0; 16]);
+    avc1.extend_from_slice(&(width as u16).to_be_bytes());
+    avc1.extend_from_slice(&(height as u16).to_be_bytes());
+    avc1.extend_from_slice(&u32s(&[0x480000, 0x480000, 0]));
+    avc1.extend_from_slice(&1u16.to_be_bytes());
+    avc1.extend_from_slice(&[0; 32]);
+    avc1.extend_from_slice(&[0x00, 0x18, 0xff, 0xff]);
+    avc1.extend_from_slice(&mp4_box(b"avcC", &avcc));
+
+    let stsd = full_box(
+        b"stsd",
+        0,
+        0,
+        &[&1u32.to_be_bytes()[..], &mp4_box(b"avc1", &avc1)].concat(),
+    );
+
+    let mut stts = u32s(&[samples.len() as u32]);
+    for sample in samples {
+        stts.extend_from_slice(&u32s(&[1, sample.duration]));
+    }
+    let stts = full_box(b"stts", 0, 0, &stts);
+
+    let sync_samples = samples
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_sync)
+        .map(|(i, _)| i as u32 + 1)
+        .collect::<Vec<_>>();
+    let mut stss = u32s(&[sync_samples.len() as u32]);
+    stss.extend_from_slice(&u32s(&sync_samples));
+    let stss = full_box(b"stss", 0, 0, &stss);
+
+    // every sample lives in one chunk
+    let stsc = full_box(b"stsc", 0, 0, &u32s(&[1, 1, samples.len() as u32, 1]));
+
+    let mut stsz = u32s(&[0, samples.len() as u32]);
+    for sample in samples {
+        stsz.extend_from_slice(&(sample.data.len() as u32).to_be_bytes());
+    }
+    let stsz = full_box(b"stsz", 0, 0, &stsz);
+
+    let stco = full_box(b"stco", 0, 0, &u32s(&[1, chunk_offset]));
+
+    let stbl = mp4_box(b"stbl", &[stsd, stts, stss, stsc, stsz, stco].concat());
+    let minf = mp4_box(b"minf", &[vmhd, dinf, stbl].concat());
+    let mdia = mp4_box(b"mdia", &[mdhd, hdlr, minf].concat());
+    let trak = mp4_box(b"trak", &[tkhd, mdia].concat());
+
+    mp4_box(b"moov", &[mvhd, trak].concat())
+}
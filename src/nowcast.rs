@@ -0,0 +1,180 @@
+use chrono::{DateTime, Utc};
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+use crate::rainfall::{rate_for, RainfallGrid, MIN_RAIN_RATE};
+
+/// Number of recent frames used to estimate how the echoes are moving.
+pub const HISTORY_FRAMES: usize = 4;
+
+/// How far ahead predicted frames are generated, in minutes.
+pub const FORECAST_STEPS: [u32; 6] = [10, 20, 30, 40, 50, 60];
+
+/// Furthest ahead rain arrival is predicted, in minutes.
+pub const HORIZON_MINUTES: u32 = 60;
+
+const BLOCK_SIZE: i64 = 32;
+/// Largest shift in pixels searched for between two frames.
+const SEARCH_RADIUS: i64 = 10;
+/// Blocks with less rain than this don't have enough texture to track.
+const MIN_RAIN_PIXELS: usize = 24;
+
+/// Echo motion in pixels per minute, positive is east and south.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Motion {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Motion {
+    fn offset(&self, minutes: u32) -> (i64, i64) {
+        (
+            (self.x * minutes as f64).round() as i64,
+            (self.y * minutes as f64).round() as i64,
+        )
+    }
+}
+
+fn rate(grid: &RainfallGrid, x: i64, y: i64) -> f32 {
+    if x < 0 || y < 0 {
+        return 0.0;
+    }
+
+    grid.rate_at(x as u32, y as u32).unwrap_or(0.0)
+}
+
+/// Finds the shift that best moves a block of `previous` onto `current`,
+/// scored by the sum of absolute differences in rain rate.
+fn match_block(
+    previous: &RainfallGrid,
+    current: &RainfallGrid,
+    block_x: i64,
+    block_y: i64,
+) -> Option<(i64, i64)> {
+    let pixels = (block_y..block_y + BLOCK_SIZE)
+        .flat_map(|y| (block_x..block_x + BLOCK_SIZE).map(move |x| (x, y)))
+        .collect::<Vec<_>>();
+
+    let raining = pixels
+        .iter()
+        .filter(|(x, y)| rate(current, *x, *y) >= MIN_RAIN_RATE)
+        .count();
+    if raining < MIN_RAIN_PIXELS {
+        return None;
+    }
+
+    let mut best = None;
+    for dy in -SEARCH_RADIUS..=SEARCH_RADIUS {
+        for dx in -SEARCH_RADIUS..=SEARCH_RADIUS {
+            let score = pixels
+                .iter()
+                .map(|(x, y)| (rate(current, *x, *y) - rate(previous, x - dx, y - dy)).abs())
+                .sum::<f32>();
+
+            // prefer the smallest shift when scores tie so empty areas don't drift
+            let distance = dx.abs() + dy.abs();
+            match best {
+                Some((best_score, best_distance, _))
+                    if score > best_score || (score == best_score && distance >= best_distance) => {
+                }
+                _ => best = Some((score, distance, (dx, dy))),
+            }
+        }
+    }
+
+    best.map(|(_, _, shift)| shift)
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
+
+/// Estimates echo motion from consecutive frames (oldest first) by block
+/// matching each pair and taking the median of every block's velocity.
+/// Returns `None` when there isn't enough rain to track.
+pub fn estimate_motion(frames: &[(DateTime<Utc>, RainfallGrid)]) -> Option<Motion> {
+    let mut velocities_x = Vec::new();
+    let mut velocities_y = Vec::new();
+
+    for pair in frames.windows(2) {
+        let (previous_time, previous) = &pair[0];
+        let (current_time, current) = &pair[1];
+        let minutes = (*current_time - *previous_time).num_minutes();
+        if minutes <= 0 {
+            continue;
+        }
+
+        for block_y in (0..current.height() as i64).step_by(BLOCK_SIZE as usize) {
+            for block_x in (0..current.width() as i64).step_by(BLOCK_SIZE as usize) {
+                if let Some((dx, dy)) = match_block(previous, current, block_x, block_y) {
+                    velocities_x.push(dx as f64 / minutes as f64);
+                    velocities_y.push(dy as f64 / minutes as f64);
+                }
+            }
+        }
+    }
+
+    if velocities_x.is_empty() {
+        return None;
+    }
+
+    Some(Motion {
+        x: median(&mut velocities_x),
+        y: median(&mut velocities_y),
+    })
+}
+
+/// Moves a radar layer along the motion, uncovered areas are left transparent.
+pub fn advect(img: &DynamicImage, motion: Motion, minutes: u32) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let (dx, dy) = motion.offset(minutes);
+    let source = img.to_rgba8();
+
+    let moved = RgbaImage::from_fn(width, height, |x, y| {
+        let from_x = x as i64 - dx;
+        let from_y = y as i64 - dy;
+        if from_x < 0 || from_y < 0 || from_x >= width as i64 || from_y >= height as i64 {
+            image::Rgba([0, 0, 0, 0])
+        } else {
+            *source.get_pixel(from_x as u32, from_y as u32)
+        }
+    });
+
+    DynamicImage::ImageRgba8(moved)
+}
+
+/// Minutes until rain reaches a pixel of the latest radar layer if the echoes
+/// keep moving the same way, 0 if it's already raining there.
+pub fn minutes_until_rain(
+    latest: &DynamicImage,
+    motion: Motion,
+    x: u32,
+    y: u32,
+    horizon_minutes: u32,
+) -> Option<u32> {
+    (0..=horizon_minutes).find(|minutes| {
+        let (dx, dy) = motion.offset(*minutes);
+        let (from_x, from_y) = (x as i64 - dx, y as i64 - dy);
+        if from_x < 0 || from_y < 0 || !latest.in_bounds(from_x as u32, from_y as u32) {
+            return false;
+        }
+
+        rate_for(latest.get_pixel(from_x as u32, from_y as u32)) >= MIN_RAIN_RATE
+    })
+}
+
+/// Decodes radar layers for [`estimate_motion`], this is slow so call it from
+/// a blocking task.
+pub fn decode_frames(
+    frames: &[(DateTime<Utc>, DynamicImage)],
+) -> Vec<(DateTime<Utc>, RainfallGrid)> {
+    frames
+        .iter()
+        .map(|(time, img)| (*time, RainfallGrid::decode(img)))
+        .collect()
+}
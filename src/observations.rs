@@ -0,0 +1,86 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use tracing::instrument;
+
+use crate::types::{Observation, ObservationsProduct};
+
+#[derive(Clone, Debug)]
+pub struct ObservationsAPI {
+    http: reqwest::Client,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ObservationsAPIError {
+    #[error("a http error occurred: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("station {0} has no observations")]
+    NoObservations(i32),
+}
+
+impl Observation {
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        NaiveDateTime::parse_from_str(&self.aifstime_utc, "%Y%m%d%H%M%S")
+            .ok()
+            .map(|time| time.and_utc())
+    }
+
+    pub fn rain_since_9am(&self) -> Option<f64> {
+        self.rain_trace.as_deref()?.parse().ok()
+    }
+}
+
+impl Default for ObservationsAPI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ObservationsAPI {
+    /// Station products are per state, e.g. `IDW60901.94608.json` for Perth.
+    const OBSERVATIONS_URL_TEMPLATE: &str =
+        "http://www.bom.gov.au/fwo/{PRODUCT}/{PRODUCT}.{STATION}.json";
+
+    pub fn new() -> Self {
+        Self {
+            // requests without a user agent are rejected
+            http: reqwest::ClientBuilder::new()
+                .user_agent("bom-discord-bot")
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// The product lists the last few days of readings, newest first.
+    #[instrument(skip(self))]
+    pub async fn get_all(
+        &self,
+        product: &str,
+        station: i32,
+    ) -> Result<Vec<Observation>, ObservationsAPIError> {
+        let url = Self::OBSERVATIONS_URL_TEMPLATE
+            .replace("{PRODUCT}", product)
+            .replace("{STATION}", &station.to_string());
+
+        let response = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<ObservationsProduct>()
+            .await?;
+
+        Ok(response.observations.data)
+    }
+
+    pub async fn get_latest(
+        &self,
+        product: &str,
+        station: i32,
+    ) -> Result<Observation, ObservationsAPIError> {
+        self.get_all(product, station)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(ObservationsAPIError::NoObservations(station))
+    }
+}
@@ -0,0 +1,247 @@
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+use tracing::instrument;
+
+use crate::{
+    forecast::{
+        compass_direction, localise, parse_time_zone, DailyForecast, ForecastDay, ForecastError,
+        ForecastHour, ForecastLocation, ForecastProvider, HourlyForecast, Provider,
+    },
+    types::OpenMeteoForecast,
+};
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+#[derive(Clone, Debug)]
+pub struct OpenMeteoAPI {
+    http: reqwest::Client,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum OpenMeteoAPIError {
+    #[error("a http error occurred: {0}")]
+    Http(#[from] reqwest::Error),
+}
+
+/// Maps WMO weather codes onto the precis codes used for emoji, with a
+/// description as open-meteo doesn't send one.
+fn precis_for_weather_code(code: i64) -> (&'static str, &'static str) {
+    match code {
+        0 => ("fine", "Clear"),
+        1 => ("mostly-fine", "Mostly clear"),
+        2 => ("partly-cloudy", "Partly cloudy"),
+        3 => ("cloudy", "Overcast"),
+        45 | 48 => ("fog", "Fog"),
+        51..=57 => ("drizzle", "Drizzle"),
+        61 | 80 => ("few-showers", "Light rain"),
+        63 | 81 => ("showers-rain", "Rain"),
+        65 | 82 => ("heavy-showers-rain", "Heavy rain"),
+        66 | 67 => ("snow-and-rain", "Freezing rain"),
+        71 | 85 => ("light-snow", "Light snow"),
+        73 | 77 => ("snow", "Snow"),
+        75 | 86 => ("heavy-snow", "Heavy snow"),
+        95 => ("thunderstorm", "Thunderstorms"),
+        96 | 99 => ("hail", "Thunderstorms with hail"),
+        _ => ("", "Unknown"),
+    }
+}
+
+/// Times are local without an offset, e.g. `2024-10-17T05:40`.
+fn local_time(
+    times: &[Option<String>],
+    i: usize,
+    time_zone: Tz,
+) -> Result<Option<DateTime<FixedOffset>>, ForecastError> {
+    times
+        .get(i)
+        .and_then(Option::as_deref)
+        .map(|time| localise(NaiveDateTime::parse_from_str(time, TIME_FORMAT)?, time_zone))
+        .transpose()
+}
+
+impl Default for OpenMeteoAPI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpenMeteoAPI {
+    const FORECAST_API: &str = "https://api.open-meteo.com/v1/forecast";
+
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::ClientBuilder::new().build().unwrap(),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_daily_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        days: i64,
+    ) -> Result<OpenMeteoForecast, OpenMeteoAPIError> {
+        let response = self
+            .http
+            .get(Self::FORECAST_API)
+            .query(&[
+                ("latitude", latitude.to_string()),
+                ("longitude", longitude.to_string()),
+                (
+                    "daily",
+                    "weather_code,temperature_2m_max,temperature_2m_min,uv_index_max,precipitation_probability_max,sunrise,sunset"
+                        .to_owned(),
+                ),
+                ("timezone", "auto".to_owned()),
+                ("forecast_days", days.to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<OpenMeteoForecast>()
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Starts from the current hour.
+    #[instrument(skip(self))]
+    pub async fn get_hourly_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        hours: i64,
+    ) -> Result<OpenMeteoForecast, OpenMeteoAPIError> {
+        let response = self
+            .http
+            .get(Self::FORECAST_API)
+            .query(&[
+                ("latitude", latitude.to_string()),
+                ("longitude", longitude.to_string()),
+                (
+                    "hourly",
+                    "temperature_2m,precipitation_probability,wind_speed_10m,wind_direction_10m"
+                        .to_owned(),
+                ),
+                ("timezone", "auto".to_owned()),
+                ("forecast_hours", hours.to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<OpenMeteoForecast>()
+            .await?;
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl ForecastProvider for OpenMeteoAPI {
+    async fn get_forecast(
+        &self,
+        location: &ForecastLocation,
+        days: i64,
+    ) -> Result<DailyForecast, ForecastError> {
+        let (Some(latitude), Some(longitude)) = (location.latitude, location.longitude) else {
+            return Err(location.not_configured(Provider::OpenMeteo));
+        };
+
+        let forecast = self.get_daily_forecast(latitude, longitude, days).await?;
+        let time_zone = parse_time_zone(&forecast.timezone)?;
+        let daily = forecast.daily;
+
+        let days = daily
+            .time
+            .iter()
+            .enumerate()
+            .map(|(i, date)| {
+                let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+                let date_time = localise(date.and_time(NaiveTime::MIN), time_zone)?;
+
+                let (precis_code, description) = daily
+                    .weather_code
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .map_or(("", "Unknown"), precis_for_weather_code);
+
+                Ok(ForecastDay {
+                    date_time,
+                    precis_code: precis_code.to_owned(),
+                    description: description.to_owned(),
+                    min: daily
+                        .temperature_2m_min
+                        .get(i)
+                        .copied()
+                        .flatten()
+                        .map(|t| t.round() as i64),
+                    max: daily
+                        .temperature_2m_max
+                        .get(i)
+                        .copied()
+                        .flatten()
+                        .map(|t| t.round() as i64),
+                    uv: daily.uv_index_max.get(i).copied().flatten(),
+                    chance_of_rain: daily
+                        .precipitation_probability_max
+                        .get(i)
+                        .copied()
+                        .flatten(),
+                    sunrise: local_time(&daily.sunrise, i, time_zone)?,
+                    sunset: local_time(&daily.sunset, i, time_zone)?,
+                })
+            })
+            .collect::<Result<Vec<_>, ForecastError>>()?;
+
+        Ok(DailyForecast {
+            location: location.name.clone(),
+            provider: Provider::OpenMeteo,
+            days,
+        })
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        location: &ForecastLocation,
+        hours: i64,
+    ) -> Result<HourlyForecast, ForecastError> {
+        let (Some(latitude), Some(longitude)) = (location.latitude, location.longitude) else {
+            return Err(location.not_configured(Provider::OpenMeteo));
+        };
+
+        let forecast = OpenMeteoAPI::get_hourly_forecast(self, latitude, longitude, hours).await?;
+        let time_zone = parse_time_zone(&forecast.timezone)?;
+        let hourly = forecast.hourly;
+
+        let hours = hourly
+            .time
+            .iter()
+            .enumerate()
+            .map(|(i, time)| {
+                let date_time = NaiveDateTime::parse_from_str(time, TIME_FORMAT)?;
+                let date_time = localise(date_time, time_zone)?;
+
+                Ok(ForecastHour {
+                    date_time,
+                    temperature: hourly.temperature_2m.get(i).copied().flatten(),
+                    chance_of_rain: hourly.precipitation_probability.get(i).copied().flatten(),
+                    wind_speed: hourly.wind_speed_10m.get(i).copied().flatten(),
+                    wind_direction: hourly
+                        .wind_direction_10m
+                        .get(i)
+                        .copied()
+                        .flatten()
+                        .map(|degrees| compass_direction(degrees).to_owned()),
+                })
+            })
+            .collect::<Result<Vec<_>, ForecastError>>()?;
+
+        Ok(HourlyForecast {
+            location: location.name.clone(),
+            provider: Provider::OpenMeteo,
+            hours,
+        })
+    }
+}
@@ -0,0 +1,273 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+
+use crate::{
+    bom::BOM,
+    forecast::{
+        DailyForecast, ForecastDay, ForecastError, ForecastLocation, ForecastProvider,
+        HourlyForecast, Provider,
+    },
+    source::SourceError,
+};
+
+const PRECIS_PATH: &str = "/anon/gen/fwo";
+
+#[derive(thiserror::Error, Debug)]
+pub enum PrecisError {
+    #[error("a image source error occurred: {0}")]
+    Source(#[from] SourceError),
+
+    #[error("a xml error occurred: {0}")]
+    Xml(#[from] quick_xml::DeError),
+
+    #[error("a date parsing error occurred: {0}")]
+    Date(#[from] chrono::ParseError),
+
+    #[error("{0} isn't in the precis forecast")]
+    UnknownArea(String),
+}
+
+/// Maps BOM forecast icon codes onto the precis codes used for emoji.
+pub fn precis_code_for_icon(icon: i64) -> &'static str {
+    match icon {
+        1 | 2 => "fine",
+        3 => "partly-cloudy",
+        4 => "cloudy",
+        6 | 10 => "fog",
+        8 => "drizzle",
+        9 | 19 => "wind",
+        11 => "few-showers",
+        12 => "showers-rain",
+        13 => "dust",
+        14 => "frost",
+        15 => "snow",
+        16 => "thunderstorm",
+        17 => "shower-or-two",
+        18 => "heavy-showers-rain",
+        _ => "",
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PrecisDay {
+    pub start_time: DateTime<FixedOffset>,
+    pub precis_code: &'static str,
+    pub precis: String,
+    /// The minimum for today is dropped from the product once the morning has passed.
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    pub chance_of_rain: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Product {
+    forecast: ProductForecast,
+}
+
+#[derive(Deserialize)]
+struct ProductForecast {
+    #[serde(rename = "area", default)]
+    areas: Vec<Area>,
+}
+
+#[derive(Deserialize)]
+struct Area {
+    #[serde(rename = "@description")]
+    description: String,
+    #[serde(rename = "forecast-period", default)]
+    periods: Vec<Period>,
+}
+
+#[derive(Deserialize)]
+struct Period {
+    #[serde(rename = "@start-time-local")]
+    start_time_local: String,
+    #[serde(rename = "$value", default)]
+    values: Vec<PeriodValue>,
+}
+
+/// Elements and text are interleaved differently between products.
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PeriodValue {
+    Element(Field),
+    Text(Field),
+}
+
+#[derive(Deserialize)]
+struct Field {
+    #[serde(rename = "@type")]
+    kind: String,
+    #[serde(rename = "$text", default)]
+    value: String,
+}
+
+impl Period {
+    fn field(&self, kind: &str) -> Option<&str> {
+        self.values.iter().find_map(|value| {
+            let (PeriodValue::Element(field) | PeriodValue::Text(field)) = value;
+            (field.kind == kind).then_some(field.value.trim())
+        })
+    }
+}
+
+/// Parses a state precis forecast product (e.g. `IDW14199.xml`) for one town.
+pub fn parse_product(xml: &str, area: &str) -> Result<Vec<PrecisDay>, PrecisError> {
+    let product: Product = quick_xml::de::from_str(xml)?;
+    let area = product
+        .forecast
+        .areas
+        .into_iter()
+        .find(|a| a.description.eq_ignore_ascii_case(area) && !a.periods.is_empty())
+        .ok_or_else(|| PrecisError::UnknownArea(area.to_owned()))?;
+
+    area.periods
+        .iter()
+        .map(|period| {
+            let icon = period
+                .field("forecast_icon_code")
+                .and_then(|icon| icon.parse().ok());
+
+            Ok(PrecisDay {
+                start_time: DateTime::parse_from_rfc3339(&period.start_time_local)?,
+                precis_code: icon.map_or("", precis_code_for_icon),
+                precis: period
+                    .field("precis")
+                    .unwrap_or_default()
+                    .trim_end_matches('.')
+                    .to_owned(),
+                min: period
+                    .field("air_temperature_minimum")
+                    .and_then(|t| t.parse().ok()),
+                max: period
+                    .field("air_temperature_maximum")
+                    .and_then(|t| t.parse().ok()),
+                chance_of_rain: period
+                    .field("probability_of_precipitation")
+                    .and_then(|chance| chance.trim_end_matches('%').parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// Forecasts from the state precis products on the BOM FTP server, these
+/// are free but only cover the bigger towns.
+#[derive(Clone)]
+pub struct PrecisForecasts {
+    bom: Arc<BOM>,
+}
+
+impl PrecisForecasts {
+    pub fn new(bom: Arc<BOM>) -> Self {
+        Self { bom }
+    }
+}
+
+#[async_trait]
+impl ForecastProvider for PrecisForecasts {
+    async fn get_forecast(
+        &self,
+        location: &ForecastLocation,
+        days: i64,
+    ) -> Result<DailyForecast, ForecastError> {
+        let (Some(product), Some(area)) = (&location.precis_product, &location.precis_area) else {
+            return Err(location.not_configured(Provider::Bom));
+        };
+
+        let xml = self
+            .bom
+            .source()
+            .retrieve(&format!("{PRECIS_PATH}/{product}.xml"))
+            .await
+            .map_err(PrecisError::from)?;
+
+        let days = parse_product(&String::from_utf8_lossy(&xml), area)?
+            .into_iter()
+            .take(days.max(0) as usize)
+            .map(|day| ForecastDay {
+                date_time: day.start_time,
+                precis_code: day.precis_code.to_owned(),
+                description: day.precis,
+                min: day.min,
+                max: day.max,
+                uv: None,
+                chance_of_rain: day.chance_of_rain,
+                sunrise: None,
+                sunset: None,
+            })
+            .collect();
+
+        Ok(DailyForecast {
+            location: location.name.clone(),
+            provider: Provider::Bom,
+            days,
+        })
+    }
+
+    /// The precis products only have a forecast per day.
+    async fn get_hourly_forecast(
+        &self,
+        _location: &ForecastLocation,
+        _hours: i64,
+    ) -> Result<HourlyForecast, ForecastError> {
+        Err(ForecastError::HourlyUnsupported(Provider::Bom.name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const PRODUCT: &str = include_str!("../tests/fixtures/ftp/anon/gen/fwo/IDW14199.xml");
+
+    #[test]
+    fn parses_product() {
+        let days = parse_product(PRODUCT, "perth").unwrap();
+
+        assert_eq!(
+            days.iter()
+                .map(|day| (
+                    day.start_time.to_rfc3339(),
+                    day.precis_code,
+                    day.precis.as_str(),
+                    day.min,
+                    day.max,
+                    day.chance_of_rain
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "2025-04-14T16:40:00+08:00".to_owned(),
+                    "partly-cloudy",
+                    "Partly cloudy",
+                    None,
+                    Some(27),
+                    Some(10)
+                ),
+                (
+                    "2025-04-15T00:00:00+08:00".to_owned(),
+                    "showers-rain",
+                    "Showers",
+                    Some(15),
+                    Some(22),
+                    Some(80)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_area() {
+        // regions are listed without any forecast periods
+        for area in ["Western Australia", "Broome"] {
+            assert!(matches!(
+                parse_product(PRODUCT, area),
+                Err(PrecisError::UnknownArea(a)) if a == area
+            ));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::{DateTime, Utc};
+
+use crate::rainfall::{self, Trend};
+
+/// Radar frames are square with the radar in the middle.
+pub const IMAGE_SIZE: u32 = 512;
+
+const KM_PER_DEGREE: f64 = 111.32;
+
+#[derive(Debug, Clone)]
+pub struct RadarSite {
+    pub name: String,
+    pub bom_radar_id: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Every range in km the site publishes a product for.
+    pub ranges: Vec<i32>,
+}
+
+/// Share of a frame's range a point can be from the radar before the next
+/// range up is used, so there's weather around it to see.
+const COVERAGE: f64 = 0.75;
+
+/// Splits a single site's product id like `IDR703` into the site and the
+/// range digit, mosaics like `IDR00004` aren't a site.
+fn split_product(bom_radar_id: &str) -> Option<(&str, char)> {
+    let digits = bom_radar_id.strip_prefix("IDR")?;
+    if digits.len() != 3 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (site, range) = bom_radar_id.split_at(5);
+    Some((site, range.chars().next()?))
+}
+
+/// Range in km covered from the centre to the edge of the frame, the last
+/// digit of the product id picks it (e.g. `IDR703` is 128km).
+pub fn range_for_product(bom_radar_id: &str) -> Option<i32> {
+    match split_product(bom_radar_id)?.1 {
+        '1' => Some(512),
+        '2' => Some(256),
+        '3' => Some(128),
+        '4' => Some(64),
+        _ => None,
+    }
+}
+
+/// The same site's product at another range, e.g. `IDR703` at 64km is `IDR704`.
+pub fn product_for_range(bom_radar_id: &str, range_km: i32) -> Option<String> {
+    let digit = match range_km {
+        512 => '1',
+        256 => '2',
+        128 => '3',
+        64 => '4',
+        _ => return None,
+    };
+    let (site, _) = split_product(bom_radar_id)?;
+
+    Some(format!("{site}{digit}"))
+}
+
+/// Finest of the ranges that comfortably fits a point this far from the
+/// radar, the widest is used right up to its edge.
+pub fn range_for_distance(ranges: &[i32], distance_km: f64) -> Option<i32> {
+    let widest = *ranges.iter().max()?;
+    let mut ranges = ranges.to_vec();
+    ranges.sort();
+
+    ranges
+        .into_iter()
+        .find(|range| distance_km <= *range as f64 * COVERAGE)
+        .or((distance_km <= widest as f64).then_some(widest))
+}
+
+/// Parses coordinates typed as `-32.05, 115.74` or `-32.05 115.74`.
+pub fn parse_coordinates(input: &str) -> Option<(f64, f64)> {
+    let mut parts = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty());
+    let latitude = parts.next()?.parse::<f64>().ok()?;
+    let longitude = parts.next()?.parse::<f64>().ok()?;
+
+    let valid = parts.next().is_none()
+        && (-90.0..=90.0).contains(&latitude)
+        && (-180.0..=180.0).contains(&longitude);
+    valid.then_some((latitude, longitude))
+}
+
+impl RadarSite {
+    pub fn range_km(&self) -> Option<f64> {
+        range_for_product(&self.bom_radar_id).map(f64::from)
+    }
+
+    pub fn km_per_pixel(&self) -> Option<f64> {
+        Some(self.range_km()? * 2.0 / IMAGE_SIZE as f64)
+    }
+
+    /// Offset of a point from the radar in km, east and south are positive
+    /// to match image coordinates.
+    fn offset_km(&self, latitude: f64, longitude: f64) -> (f64, f64) {
+        let east = (longitude - self.longitude) * KM_PER_DEGREE * self.latitude.to_radians().cos();
+        let south = (self.latitude - latitude) * KM_PER_DEGREE;
+        (east, south)
+    }
+
+    pub fn distance_km(&self, latitude: f64, longitude: f64) -> f64 {
+        let (east, south) = self.offset_km(latitude, longitude);
+        east.hypot(south)
+    }
+
+    /// Pixel in a frame from this radar that covers the point, `None` if it's
+    /// outside the frame.
+    pub fn pixel_for(&self, latitude: f64, longitude: f64) -> Option<(u32, u32)> {
+        let km_per_pixel = self.km_per_pixel()?;
+        let (east, south) = self.offset_km(latitude, longitude);
+        let centre = IMAGE_SIZE as f64 / 2.0;
+
+        let x = (centre + east / km_per_pixel).floor();
+        let y = (centre + south / km_per_pixel).floor();
+        let bounds = 0.0..IMAGE_SIZE as f64;
+        if !bounds.contains(&x) || !bounds.contains(&y) {
+            return None;
+        }
+
+        Some((x as u32, y as u32))
+    }
+}
+
+/// Picks the closest radar that can see the point, preferring the finer
+/// product when a site has more than one.
+pub fn nearest_site(sites: Vec<RadarSite>, latitude: f64, longitude: f64) -> Option<RadarSite> {
+    sites
+        .into_iter()
+        .filter(|site| site.pixel_for(latitude, longitude).is_some())
+        .min_by(|a, b| {
+            let distance_a = a.distance_km(latitude, longitude);
+            let distance_b = b.distance_km(latitude, longitude);
+            distance_a.total_cmp(&distance_b).then(
+                a.range_km()
+                    .unwrap_or(0.0)
+                    .total_cmp(&b.range_km().unwrap_or(0.0)),
+            )
+        })
+}
+
+/// Picks the closest site to the point and the finest of its products that
+/// covers it, the site's name is kept.
+pub fn best_product(sites: Vec<RadarSite>, latitude: f64, longitude: f64) -> Option<RadarSite> {
+    let site = sites.into_iter().min_by(|a, b| {
+        a.distance_km(latitude, longitude)
+            .total_cmp(&b.distance_km(latitude, longitude))
+    })?;
+    let range_km = range_for_distance(&site.ranges, site.distance_km(latitude, longitude))?;
+
+    Some(RadarSite {
+        bom_radar_id: product_for_range(&site.bom_radar_id, range_km)?,
+        ..site
+    })
+}
+
+/// Rain rates read from consecutive frames at a single point.
+pub struct PointRainfall {
+    pub site: RadarSite,
+    /// Oldest first.
+    pub samples: Vec<(DateTime<Utc>, f32)>,
+    /// When the nowcast expects rain to reach the point, 0 if it's raining now.
+    pub expected_in_minutes: Option<u32>,
+}
+
+impl PointRainfall {
+    pub fn current(&self) -> Option<(DateTime<Utc>, f32)> {
+        self.samples.last().copied()
+    }
+
+    pub fn trend(&self) -> Trend {
+        let rates = self
+            .samples
+            .iter()
+            .map(|(_, rate)| *rate)
+            .collect::<Vec<_>>();
+        rainfall::trend(&rates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("-32.05, 115.74", Some((-32.05, 115.74)))]
+    #[case("-32.05 115.74", Some((-32.05, 115.74)))]
+    #[case("  -32.05,115.74  ", Some((-32.05, 115.74)))]
+    #[case("-32.05", None)]
+    #[case("-32.05, 115.74, 3", None)]
+    #[case("-95, 115.74", None)]
+    #[case("-32.05, 181", None)]
+    #[case("Perth", None)]
+    fn parses_coordinates(#[case] input: &str, #[case] expected: Option<(f64, f64)>) {
+        assert_eq!(parse_coordinates(input), expected);
+    }
+
+    #[rstest]
+    #[case("IDR703", 64, Some("IDR704"))]
+    #[case("IDR703", 128, Some("IDR703"))]
+    #[case("IDR703", 256, Some("IDR702"))]
+    #[case("IDR703", 512, Some("IDR701"))]
+    #[case("IDR703", 100, None)]
+    #[case("IDR00004", 512, None)]
+    #[case("", 128, None)]
+    fn product_at_range(
+        #[case] bom_radar_id: &str,
+        #[case] range_km: i32,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(
+            product_for_range(bom_radar_id, range_km).as_deref(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("IDR701", Some(512))]
+    #[case("IDR704", Some(64))]
+    #[case("IDR00004", None)]
+    #[case("IDR7A3", None)]
+    #[case("IDR705", None)]
+    fn range_of_product(#[case] bom_radar_id: &str, #[case] expected: Option<i32>) {
+        assert_eq!(range_for_product(bom_radar_id), expected);
+    }
+
+    #[rstest]
+    #[case(&[64, 128, 256, 512], 10.0, Some(64))]
+    #[case(&[64, 128, 256, 512], 48.0, Some(64))]
+    #[case(&[64, 128, 256, 512], 49.0, Some(128))]
+    #[case(&[512, 128, 256, 64], 150.0, Some(256))]
+    #[case(&[64, 128, 256, 512], 450.0, Some(512))]
+    #[case(&[64, 128, 256, 512], 600.0, None)]
+    #[case(&[128], 120.0, Some(128))]
+    #[case(&[], 10.0, None)]
+    fn range_at_distance(
+        #[case] ranges: &[i32],
+        #[case] distance_km: f64,
+        #[case] expected: Option<i32>,
+    ) {
+        assert_eq!(range_for_distance(ranges, distance_km), expected);
+    }
+}
@@ -0,0 +1,213 @@
+use image::{DynamicImage, GenericImageView, Rgba};
+
+/// Colours used by the rain rate legend (`IDR.legend.0.png`), each one is the
+/// lower bound of its band in mm/h.
+pub const RAIN_RATE_PALETTE: [([u8; 3], f32); 15] = [
+    ([245, 245, 255], 0.2),
+    ([180, 180, 255], 0.5),
+    ([120, 120, 255], 1.5),
+    ([20, 20, 255], 2.5),
+    ([0, 216, 195], 4.0),
+    ([0, 150, 144], 6.0),
+    ([0, 102, 102], 10.0),
+    ([255, 255, 0], 15.0),
+    ([255, 200, 0], 20.0),
+    ([255, 150, 0], 35.0),
+    ([255, 100, 0], 50.0),
+    ([255, 0, 0], 80.0),
+    ([200, 0, 0], 120.0),
+    ([120, 0, 0], 200.0),
+    ([40, 0, 0], 360.0),
+];
+
+/// Lowest rate the radar reports, anything under this is dry.
+pub const MIN_RAIN_RATE: f32 = RAIN_RATE_PALETTE[0].1;
+
+/// Squared distance a colour can be from a palette entry and still match it,
+/// resized frames don't always keep the exact palette colours.
+const MATCH_TOLERANCE: u32 = 3 * 12 * 12;
+
+fn distance(colour: &[u8; 3], pixel: Rgba<u8>) -> u32 {
+    colour
+        .iter()
+        .zip(pixel.0.iter())
+        .map(|(a, b)| (*a as i32 - *b as i32).pow(2) as u32)
+        .sum()
+}
+
+/// Rain rate in mm/h for a single radar pixel, 0 when there's no echo.
+pub fn rate_for(pixel: Rgba<u8>) -> f32 {
+    if pixel[3] == 0 {
+        return 0.0;
+    }
+
+    RAIN_RATE_PALETTE
+        .iter()
+        .map(|(colour, rate)| (distance(colour, pixel), *rate))
+        .filter(|(distance, _)| *distance <= MATCH_TOLERANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map_or(0.0, |(_, rate)| rate)
+}
+
+/// Rates in the palette whose colour isn't anywhere in the legend image. If
+/// BOM changes the legend this stops being empty, rather than frames
+/// quietly decoding to the wrong rates.
+pub fn missing_from_legend(legend: &DynamicImage) -> Vec<f32> {
+    let legend = legend.to_rgba8();
+
+    RAIN_RATE_PALETTE
+        .iter()
+        .filter(|(colour, _)| {
+            !legend
+                .pixels()
+                .any(|pixel| pixel[3] != 0 && distance(colour, *pixel) <= MATCH_TOLERANCE)
+        })
+        .map(|(_, rate)| *rate)
+        .collect()
+}
+
+/// A radar frame decoded into rain rates, row major from the top left.
+pub struct RainfallGrid {
+    width: u32,
+    height: u32,
+    rates: Vec<f32>,
+}
+
+impl RainfallGrid {
+    pub fn decode(img: &DynamicImage) -> Self {
+        let (width, height) = img.dimensions();
+        let rates = img.to_rgba8().pixels().map(|p| rate_for(*p)).collect();
+
+        Self {
+            width,
+            height,
+            rates,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn rate_at(&self, x: u32, y: u32) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.rates
+            .get((y as usize) * (self.width as usize) + x as usize)
+            .copied()
+    }
+
+    pub fn max_rate(&self) -> f32 {
+        self.rates.iter().copied().fold(0.0, f32::max)
+    }
+
+    /// Fraction of the frame with at least `threshold` mm/h falling.
+    pub fn coverage(&self, threshold: f32) -> f32 {
+        if self.rates.is_empty() {
+            return 0.0;
+        }
+
+        let raining = self.rates.iter().filter(|r| **r >= threshold).count();
+        raining as f32 / self.rates.len() as f32
+    }
+}
+
+/// Describes a rain rate the same way the legend groups its bands.
+pub fn intensity(rate: f32) -> &'static str {
+    match rate {
+        r if r < MIN_RAIN_RATE => "none",
+        r if r < 2.5 => "light",
+        r if r < 10.0 => "moderate",
+        r if r < 50.0 => "heavy",
+        _ => "very heavy",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Increasing,
+    Steady,
+    Easing,
+}
+
+impl Trend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Increasing => "increasing",
+            Self::Steady => "steady",
+            Self::Easing => "easing",
+        }
+    }
+}
+
+/// Compares the latest rate against the average of the ones before it.
+pub fn trend(rates: &[f32]) -> Trend {
+    let Some((latest, earlier)) = rates.split_last() else {
+        return Trend::Steady;
+    };
+
+    if earlier.is_empty() {
+        return Trend::Steady;
+    }
+
+    let average = earlier.iter().sum::<f32>() / earlier.len() as f32;
+    if *latest > average {
+        Trend::Increasing
+    } else if *latest < average {
+        Trend::Easing
+    } else {
+        Trend::Steady
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Rgba([245, 245, 255, 255]), 0.2)]
+    #[case(Rgba([255, 255, 0, 255]), 15.0)]
+    #[case(Rgba([40, 0, 0, 255]), 360.0)]
+    // resized frames blend colours a little
+    #[case(Rgba([250, 250, 5, 255]), 15.0)]
+    #[case(Rgba([255, 255, 0, 0]), 0.0)]
+    #[case(Rgba([128, 128, 128, 255]), 0.0)]
+    fn rate_for_pixel(#[case] pixel: Rgba<u8>, #[case] expected: f32) {
+        assert_eq!(rate_for(pixel), expected);
+    }
+
+    #[test]
+    fn legend_with_every_colour() {
+        let mut legend = RgbaImage::new(RAIN_RATE_PALETTE.len() as u32, 1);
+        for (x, ([r, g, b], _)) in RAIN_RATE_PALETTE.iter().enumerate() {
+            legend.put_pixel(x as u32, 0, Rgba([*r, *g, *b, 255]));
+        }
+
+        assert_eq!(
+            missing_from_legend(&DynamicImage::ImageRgba8(legend)),
+            Vec::<f32>::new()
+        );
+    }
+
+    #[test]
+    fn legend_without_the_colours() {
+        let legend = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+
+        assert_eq!(
+            missing_from_legend(&DynamicImage::ImageRgba8(legend)),
+            RAIN_RATE_PALETTE
+                .iter()
+                .map(|(_, rate)| *rate)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, DynamicImage, GenericImageView,
+};
+use serde::Deserialize;
+
+use crate::mp4;
+
+#[derive(thiserror::Error, Debug)]
+pub enum RenderError {
+    #[error("there are no frames to render")]
+    NoFrames,
+
+    #[error("a image error occurred: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("a png encoding error occurred: {0}")]
+    Png(#[from] png::EncodingError),
+
+    #[error("a webp encoding error occurred: {0}")]
+    WebP(String),
+
+    #[error("a mp4 encoding error occurred: {0}")]
+    Mp4(#[from] mp4::Mp4Error),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Gif,
+    WebP,
+    Apng,
+    Mp4,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 4] = [Self::Gif, Self::WebP, Self::Apng, Self::Mp4];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::WebP => "webp",
+            Self::Apng => "apng",
+            Self::Mp4 => "mp4",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|f| f.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::WebP => "webp",
+            Self::Apng => "png",
+            Self::Mp4 => "mp4",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Gif => "image/gif",
+            Self::WebP => "image/webp",
+            Self::Apng => "image/apng",
+            Self::Mp4 => "video/mp4",
+        }
+    }
+
+    /// Videos can't be shown as an embed image, only as an attachment.
+    pub fn is_video(&self) -> bool {
+        matches!(self, Self::Mp4)
+    }
+}
+
+/// How an animated loop is put together, each generator has its own defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Number of most recent frames to use, `None` uses all of them.
+    pub frame_count: Option<usize>,
+    pub frame_delay_ms: u32,
+    /// Extra time the last frame stays up before the loop restarts.
+    pub last_frame_hold_ms: u32,
+    /// Times the loop plays, `None` loops forever.
+    pub loop_count: Option<u16>,
+}
+
+impl RenderOptions {
+    pub const RADAR: Self = Self {
+        frame_count: Some(7),
+        frame_delay_ms: 350,
+        last_frame_hold_ms: 0,
+        loop_count: None,
+    };
+
+    pub const TIMELAPSE: Self = Self {
+        frame_count: None,
+        frame_delay_ms: 10,
+        last_frame_hold_ms: 0,
+        loop_count: None,
+    };
+
+    pub const SATELLITE: Self = Self {
+        frame_count: Some(30),
+        frame_delay_ms: 215,
+        last_frame_hold_ms: 0,
+        loop_count: None,
+    };
+
+    const MAX_FRAMES: i64 = 300;
+    const MAX_DELAY_MS: i64 = 5000;
+    const MAX_HOLD_MS: i64 = 10000;
+    const MAX_LOOPS: i64 = 100;
+
+    /// Applies optional user supplied values, clamped to something sane.
+    /// A loop count of 0 loops forever.
+    pub fn with_overrides(
+        self,
+        frame_count: Option<i64>,
+        frame_delay_ms: Option<i64>,
+        last_frame_hold_ms: Option<i64>,
+        loop_count: Option<i64>,
+    ) -> Self {
+        Self {
+            frame_count: frame_count
+                .map(|f| f.clamp(1, Self::MAX_FRAMES) as usize)
+                .or(self.frame_count),
+            frame_delay_ms: frame_delay_ms
+                .map(|d| d.clamp(10, Self::MAX_DELAY_MS) as u32)
+                .unwrap_or(self.frame_delay_ms),
+            last_frame_hold_ms: last_frame_hold_ms
+                .map(|h| h.clamp(0, Self::MAX_HOLD_MS) as u32)
+                .unwrap_or(self.last_frame_hold_ms),
+            loop_count: match loop_count {
+                Some(0) => None,
+                Some(l) => Some(l.clamp(1, Self::MAX_LOOPS) as u16),
+                None => self.loop_count,
+            },
+        }
+    }
+
+    /// Short identifier used to keep differently rendered loops apart in the bucket.
+    pub fn cache_key(&self) -> String {
+        let frames = self
+            .frame_count
+            .map_or_else(|| "all".to_owned(), |f| f.to_string());
+        let loops = self
+            .loop_count
+            .map_or_else(|| "inf".to_owned(), |l| l.to_string());
+
+        format!(
+            "{frames}f.{}d.{}h.{loops}l",
+            self.frame_delay_ms, self.last_frame_hold_ms
+        )
+    }
+
+    /// Picks the frames to render from a sorted list.
+    pub fn select<'a, T>(&self, items: &'a [T]) -> &'a [T] {
+        match self.frame_count {
+            Some(count) => &items[items.len().saturating_sub(count)..],
+            None => items,
+        }
+    }
+
+    fn delay_ms_for(&self, index: usize, total: usize) -> u32 {
+        if index + 1 == total {
+            self.frame_delay_ms + self.last_frame_hold_ms
+        } else {
+            self.frame_delay_ms
+        }
+    }
+}
+
+/// Encodes the frames in the requested format, this is slow so call it from a
+/// blocking task. Loop counts are ignored for mp4.
+pub fn encode(
+    images: Vec<DynamicImage>,
+    options: &RenderOptions,
+    format: OutputFormat,
+) -> Result<Vec<u8>, RenderError> {
+    if images.is_empty() {
+        return Err(RenderError::NoFrames);
+    }
+
+    match format {
+        OutputFormat::Gif => encode_gif(images, options),
+        OutputFormat::WebP => encode_webp(images, options),
+        OutputFormat::Apng => encode_apng(images, options),
+        OutputFormat::Mp4 => {
+            let total = images.len();
+            let durations = (0..total)
+                .map(|i| options.delay_ms_for(i, total))
+                .collect::<Vec<_>>();
+
+            Ok(mp4::encode_h264(images, &durations)?)
+        }
+    }
+}
+
+fn encode_gif(images: Vec<DynamicImage>, options: &RenderOptions) -> Result<Vec<u8>, RenderError> {
+    let total = images.len();
+    let frames = images.into_iter().enumerate().map(|(i, img)| {
+        let delay = Delay::from_numer_denom_ms(options.delay_ms_for(i, total), 1);
+        image::Frame::from_parts(img.to_rgba8(), 0, 0, delay)
+    });
+
+    // gif counts repeats after the first play, which webp and apng don't, and
+    // leaves the loop extension out for 0 so the gif plays once
+    let repeat = match options.loop_count {
+        Some(count) => Repeat::Finite(count.saturating_sub(1)),
+        None => Repeat::Infinite,
+    };
+
+    let mut final_gif = Vec::<u8>::new();
+    let mut final_gif_cursor = std::io::Cursor::new(&mut final_gif);
+    let mut gif_encoder = GifEncoder::new_with_speed(&mut final_gif_cursor, 1);
+    gif_encoder.set_repeat(repeat)?;
+    gif_encoder.encode_frames(frames)?;
+
+    drop(gif_encoder);
+
+    Ok(final_gif)
+}
+
+fn encode_webp(images: Vec<DynamicImage>, options: &RenderOptions) -> Result<Vec<u8>, RenderError> {
+    let (width, height) = images[0].dimensions();
+    let frames = images.into_iter().map(|i| i.to_rgba8()).collect::<Vec<_>>();
+
+    let mut config =
+        webp::WebPConfig::new().map_err(|_| RenderError::WebP("invalid config".to_owned()))?;
+    config.lossless = 0;
+    config.quality = 75.0;
+
+    let mut encoder = webp::AnimEncoder::new(width, height, &config);
+    encoder.set_loop_count(options.loop_count.map_or(0, i32::from));
+
+    let mut timestamp_ms = 0;
+    for (i, frame) in frames.iter().enumerate() {
+        encoder.add_frame(webp::AnimFrame::from_rgba(
+            frame.as_raw(),
+            width,
+            height,
+            timestamp_ms,
+        ));
+        timestamp_ms += options.delay_ms_for(i, frames.len()) as i32;
+    }
+
+    let final_webp = encoder
+        .try_encode()
+        .map_err(|e| RenderError::WebP(format!("{e:?}")))?;
+
+    Ok(final_webp.to_vec())
+}
+
+fn encode_apng(images: Vec<DynamicImage>, options: &RenderOptions) -> Result<Vec<u8>, RenderError> {
+    let (width, height) = images[0].dimensions();
+    let total = images.len();
+
+    let mut final_png = Vec::<u8>::new();
+    let mut encoder = png::Encoder::new(&mut final_png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(total as u32, options.loop_count.map_or(0, u32::from))?;
+
+    let mut writer = encoder.write_header()?;
+    for (i, img) in images.into_iter().enumerate() {
+        let delay = options.delay_ms_for(i, total).min(u16::MAX as u32) as u16;
+        writer.set_frame_delay(delay, 1000)?;
+        writer.write_image_data(img.to_rgba8().as_raw())?;
+    }
+
+    writer.finish()?;
+
+    Ok(final_png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[test]
+    fn overrides_are_clamped() {
+        assert_eq!(
+            RenderOptions::RADAR.with_overrides(Some(1000), Some(1), Some(-5), Some(500)),
+            RenderOptions {
+                frame_count: Some(300),
+                frame_delay_ms: 10,
+                last_frame_hold_ms: 0,
+                loop_count: Some(100),
+            }
+        );
+    }
+
+    #[test]
+    fn missing_overrides_keep_defaults() {
+        assert_eq!(
+            RenderOptions::SATELLITE.with_overrides(None, None, None, None),
+            RenderOptions::SATELLITE
+        );
+    }
+
+    #[test]
+    fn zero_loops_forever() {
+        let options = RenderOptions::RADAR.with_overrides(None, None, None, Some(3));
+        assert_eq!(options.loop_count, Some(3));
+        assert_eq!(
+            options.with_overrides(None, None, None, Some(0)).loop_count,
+            None
+        );
+    }
+
+    #[rstest]
+    #[case(Some(3), &[3, 4, 5])]
+    #[case(Some(10), &[1, 2, 3, 4, 5])]
+    #[case(None, &[1, 2, 3, 4, 5])]
+    fn selects_latest(#[case] frame_count: Option<usize>, #[case] expected: &[i32]) {
+        let options = RenderOptions {
+            frame_count,
+            ..RenderOptions::RADAR
+        };
+
+        assert_eq!(options.select(&[1, 2, 3, 4, 5]), expected);
+    }
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use twilight_http::Client as HttpClient;
+use twilight_model::id::Id;
+
+use crate::{
+    bom::BOM,
+    forecast::{forecast_embed, Forecaster},
+};
+
+/// A post is still sent if the bot was down at the scheduled time, as long as
+/// it comes back within this window.
+const LATE_WINDOW_MINUTES: i64 = 30;
+
+/// The local date of the post that's due at `now`, if any. Yesterday's post
+/// can still be due just after midnight.
+fn due_post_date(now: DateTime<Utc>, post_time: NaiveTime, time_zone: Tz) -> Option<NaiveDate> {
+    let today = now.with_timezone(&time_zone).date_naive();
+
+    [Some(today), today.checked_sub_days(Days::new(1))]
+        .into_iter()
+        .flatten()
+        .find(|date| {
+            let Some(scheduled) = time_zone
+                .from_local_datetime(&date.and_time(post_time))
+                .earliest()
+            else {
+                return false;
+            };
+
+            let since_post_time = now - scheduled.to_utc();
+            since_post_time >= TimeDelta::zero()
+                && since_post_time <= TimeDelta::minutes(LATE_WINDOW_MINUTES)
+        })
+}
+
+/// Posts every scheduled forecast that is due and hasn't gone out for that
+/// day in its own time zone.
+pub async fn post_due_forecasts(
+    bom: &BOM,
+    forecaster: &Forecaster,
+    http: &HttpClient,
+) -> Result<(), anyhow::Error> {
+    let db = bom.db();
+    let schedules = sqlx::query!("SELECT * FROM forecast_schedules")
+        .fetch_all(db)
+        .await?;
+
+    let now = Utc::now();
+    for schedule in schedules {
+        let time_zone = schedule.time_zone.parse().unwrap_or(Tz::UTC);
+        let Some(post_date) = due_post_date(now, schedule.post_time, time_zone)
+            .filter(|date| schedule.last_posted_on != Some(*date))
+        else {
+            continue;
+        };
+
+        tracing::info!(
+            "posting forecast schedule {} to {}",
+            schedule.id,
+            schedule.channel_id
+        );
+
+        let post = async {
+            let forecast = forecaster
+                .get_forecast(&schedule.location, schedule.days as i64)
+                .await?;
+            let embed = forecast_embed(&forecast).build();
+
+            http.create_message(Id::new(schedule.channel_id as u64))
+                .embeds(&[embed])
+                .await?;
+
+            Ok::<(), anyhow::Error>(())
+        };
+
+        if let Err(e) = post.await {
+            tracing::error!("error posting forecast schedule {}: {e}", schedule.id);
+            continue;
+        }
+
+        sqlx::query!(
+            "UPDATE forecast_schedules SET last_posted_on = ($1) WHERE id = ($2)",
+            post_date,
+            schedule.id
+        )
+        .execute(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn perth(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Tz::Australia__Perth
+            .with_ymd_and_hms(2025, 4, day, hour, minute, 0)
+            .unwrap()
+            .to_utc()
+    }
+
+    #[rstest]
+    #[case(perth(14, 18, 0), (18, 0), Some(14))]
+    #[case(perth(14, 18, 30), (18, 0), Some(14))]
+    #[case(perth(14, 17, 59), (18, 0), None)]
+    #[case(perth(14, 18, 31), (18, 0), None)]
+    #[case(perth(15, 0, 10), (23, 50), Some(14))]
+    #[case(perth(15, 0, 30), (23, 50), None)]
+    fn due_in_window(
+        #[case] now: DateTime<Utc>,
+        #[case] (hour, minute): (u32, u32),
+        #[case] expected_day: Option<u32>,
+    ) {
+        assert_eq!(
+            due_post_date(
+                now,
+                NaiveTime::from_hms_opt(hour, minute, 0).unwrap(),
+                Tz::Australia__Perth
+            ),
+            expected_day.and_then(|day| NaiveDate::from_ymd_opt(2025, 4, day))
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use std::path::PathBuf;
+
+use async_ftp::{FtpError, FtpStream};
+use async_trait::async_trait;
+use regex::Regex;
+use tokio::{io::AsyncReadExt, sync::Mutex};
+
+#[derive(thiserror::Error, Debug)]
+pub enum SourceError {
+    #[error("a ftp response error occurred: {0}")]
+    Ftp(#[from] FtpError),
+
+    #[error("a http error occurred: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("an io error occurred: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Somewhere BOM products can be listed and downloaded from, paths are always
+/// in the layout of the BOM FTP server (e.g. `/anon/gen/radar/IDR703.T.202504141204.png`).
+#[async_trait]
+pub trait ImageSource: Send + Sync {
+    /// Returns the full path of every file in `dir`.
+    async fn list(&self, dir: &str) -> Result<Vec<String>, SourceError>;
+
+    async fn retrieve(&self, path: &str) -> Result<Vec<u8>, SourceError>;
+}
+
+/// Picks a source from a url, `ftp://` and `http(s)://` urls are used as is and
+/// anything else is treated as a local directory.
+pub fn from_url(url: &str) -> Box<dyn ImageSource> {
+    if let Some(host) = url.strip_prefix("ftp://") {
+        let host = host.trim_end_matches('/');
+        let (host, port) = match host.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().unwrap_or(21)),
+            None => (host, 21),
+        };
+
+        Box::new(FtpImageSource::new(host, port))
+    } else if url.starts_with("http://") || url.starts_with("https://") {
+        Box::new(HttpImageSource::new(url))
+    } else {
+        Box::new(LocalDirImageSource::new(url))
+    }
+}
+
+pub struct FtpImageSource {
+    host: String,
+    port: u16,
+    session: Mutex<Option<FtpStream>>,
+}
+
+impl FtpImageSource {
+    pub const BOM_HOST: &str = "ftp.bom.gov.au";
+
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: host.to_owned(),
+            port,
+            session: Mutex::new(None),
+        }
+    }
+
+    pub fn bom() -> Self {
+        Self::new(Self::BOM_HOST, 21)
+    }
+
+    async fn connect(&self) -> Result<FtpStream, FtpError> {
+        let addr = (self.host.as_str(), self.port);
+        let mut ftp_client = FtpStream::connect(addr).await?;
+        ftp_client.login("anonymous", "anonymous").await?;
+        Ok(ftp_client)
+    }
+}
+
+#[async_trait]
+impl ImageSource for FtpImageSource {
+    async fn list(&self, dir: &str) -> Result<Vec<String>, SourceError> {
+        let mut session = self.session.lock().await;
+        if let Some(ftp_client) = session.as_mut() {
+            match ftp_client.nlst(Some(dir)).await {
+                Ok(files) => return Ok(files),
+                Err(e) => tracing::warn!("ftp session failed, reconnecting: {e}"),
+            }
+        }
+
+        let ftp_client = session.insert(self.connect().await?);
+        Ok(ftp_client.nlst(Some(dir)).await?)
+    }
+
+    async fn retrieve(&self, path: &str) -> Result<Vec<u8>, SourceError> {
+        let mut session = self.session.lock().await;
+        let mut buffer = Vec::new();
+        if let Some(ftp_client) = session.as_mut() {
+            match ftp_client.simple_retr(path).await {
+                Ok(mut file) => {
+                    file.read_to_end(&mut buffer).await?;
+                    return Ok(buffer);
+                }
+                Err(e) => tracing::warn!("ftp session failed, reconnecting: {e}"),
+            }
+        }
+
+        let ftp_client = session.insert(self.connect().await?);
+        ftp_client
+            .simple_retr(path)
+            .await?
+            .read_to_end(&mut buffer)
+            .await?;
+
+        Ok(buffer)
+    }
+}
+
+/// Serves files from a directory laid out like the FTP server, used for fixtures.
+pub struct LocalDirImageSource {
+    root: PathBuf,
+}
+
+impl LocalDirImageSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf {
+        self.root.join(path.trim_start_matches('/'))
+    }
+}
+
+#[async_trait]
+impl ImageSource for LocalDirImageSource {
+    async fn list(&self, dir: &str) -> Result<Vec<String>, SourceError> {
+        let dir = dir.trim_end_matches('/');
+        let mut entries = tokio::fs::read_dir(self.resolve(dir)).await?;
+        let mut files = Vec::new();
+
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+
+            if let Some(name) = entry.file_name().to_str() {
+                files.push(format!("{dir}/{name}"));
+            }
+        }
+
+        Ok(files)
+    }
+
+    async fn retrieve(&self, path: &str) -> Result<Vec<u8>, SourceError> {
+        Ok(tokio::fs::read(self.resolve(path)).await?)
+    }
+}
+
+/// Reads from a http mirror of the FTP server, listings are scraped from the
+/// directory index page.
+pub struct HttpImageSource {
+    http: reqwest::Client,
+    base_url: String,
+    match_link: Regex,
+}
+
+impl HttpImageSource {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: reqwest::ClientBuilder::new().build().unwrap(),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            match_link: Regex::new(r#"href="(?<link>[^"?#]+)""#).unwrap(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageSource for HttpImageSource {
+    async fn list(&self, dir: &str) -> Result<Vec<String>, SourceError> {
+        let dir = dir.trim_end_matches('/');
+        let index = self
+            .http
+            .get(format!("{}{dir}/", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let files = self
+            .match_link
+            .captures_iter(&index)
+            .map(|caps| caps["link"].to_owned())
+            .filter(|link| !link.ends_with('/'))
+            .filter_map(|link| link.rsplit('/').next().map(|name| format!("{dir}/{name}")))
+            .collect();
+
+        Ok(files)
+    }
+
+    async fn retrieve(&self, path: &str) -> Result<Vec<u8>, SourceError> {
+        let bytes = self
+            .http
+            .get(format!("{}{path}", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn fixtures() -> LocalDirImageSource {
+        LocalDirImageSource::new(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ftp"),
+        )
+    }
+
+    #[tokio::test]
+    async fn lists_in_ftp_layout() {
+        let mut files = fixtures().list("/anon/gen/radar/").await.unwrap();
+        files.sort();
+
+        assert_eq!(
+            files,
+            vec![
+                "/anon/gen/radar/IDR702.T.202504141205.png",
+                "/anon/gen/radar/IDR702.T.202504141211.png",
+                "/anon/gen/radar/IDR703.T.202504141204.png",
+                "/anon/gen/radar/IDR703.T.202504141210.png",
+                "/anon/gen/radar/IDR703.T.202504141216.png",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn retrieves_from_root() {
+        let legend = fixtures()
+            .retrieve("/anon/gen/radar_transparencies/IDR.legend.0.png")
+            .await
+            .unwrap();
+
+        assert!(legend.starts_with(b"\x89PNG"));
+    }
+
+    #[tokio::test]
+    async fn missing_file() {
+        assert!(matches!(
+            fixtures().retrieve("/anon/gen/radar/IDR000.png").await,
+            Err(SourceError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use async_trait::async_trait;
+use s3::error::S3Error;
+
+#[derive(thiserror::Error, Debug)]
+pub enum StorageError {
+    #[error("object not found: {0}")]
+    NotFound(String),
+
+    #[error("a s3 error occurred: {0}")]
+    S3(#[from] S3Error),
+
+    #[error("an io error occurred: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Where cached frames and generated images live, keys are `/` separated
+/// (e.g. `radar_cache/IDR703.T.202504141204.png`).
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    async fn exists(&self, key: &str) -> Result<bool, StorageError>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError>;
+
+    async fn put(&self, key: &str, bytes: &[u8], content_type: &str) -> Result<(), StorageError>;
+
+    /// Returns every key starting with `prefix`.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError>;
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+pub struct S3ObjectStore {
+    bucket: Box<s3::Bucket>,
+}
+
+impl S3ObjectStore {
+    pub fn new(bucket: Box<s3::Bucket>) -> Self {
+        Self { bucket }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3ObjectStore {
+    async fn exists(&self, key: &str) -> Result<bool, StorageError> {
+        Ok(self.bucket.head_object(key).await.is_ok())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        Ok(self.bucket.get_object(key).await?.to_vec())
+    }
+
+    async fn put(&self, key: &str, bytes: &[u8], content_type: &str) -> Result<(), StorageError> {
+        self.bucket
+            .put_object_with_content_type(key, bytes, content_type)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let keys = self
+            .bucket
+            .list(prefix.to_owned(), None)
+            .await?
+            .into_iter()
+            .flat_map(|i| i.contents)
+            .map(|o| o.key)
+            .collect();
+
+        Ok(keys)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.bucket.delete_object(key).await?;
+        Ok(())
+    }
+}
+
+/// Stores objects as files under a directory, content types are not kept.
+pub struct LocalObjectStore {
+    root: PathBuf,
+}
+
+impl LocalObjectStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn key_for(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let parts = relative
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(parts.join("/"))
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalObjectStore {
+    async fn exists(&self, key: &str) -> Result<bool, StorageError> {
+        Ok(tokio::fs::try_exists(self.root.join(key)).await?)
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        match tokio::fs::read(self.root.join(key)).await {
+            Ok(bytes) => Ok(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(StorageError::NotFound(key.to_owned()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put(&self, key: &str, bytes: &[u8], _content_type: &str) -> Result<(), StorageError> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        tokio::fs::write(path, bytes).await?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let start = match prefix.rsplit_once('/') {
+            Some((dir, _)) => self.root.join(dir),
+            None => self.root.clone(),
+        };
+
+        let mut keys = Vec::new();
+        let mut pending = vec![start];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_dir() {
+                    pending.push(entry.path());
+                } else if let Some(key) = self.key_for(&entry.path()) {
+                    if key.starts_with(prefix) {
+                        keys.push(key);
+                    }
+                }
+            }
+        }
+
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        tokio::fs::remove_file(self.root.join(key)).await?;
+        Ok(())
+    }
+}
+
+/// Keeps everything in memory, nothing survives a restart.
+#[derive(Default)]
+pub struct MemoryObjectStore {
+    objects: RwLock<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryObjectStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for MemoryObjectStore {
+    async fn exists(&self, key: &str) -> Result<bool, StorageError> {
+        Ok(self.objects.read().unwrap().contains_key(key))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        self.objects
+            .read()
+            .unwrap()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(key.to_owned()))
+    }
+
+    async fn put(&self, key: &str, bytes: &[u8], _content_type: &str) -> Result<(), StorageError> {
+        self.objects
+            .write()
+            .unwrap()
+            .insert(key.to_owned(), bytes.to_vec());
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let keys = self
+            .objects
+            .read()
+            .unwrap()
+            .range(prefix.to_owned()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        Ok(keys)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.objects.write().unwrap().remove(key);
+        Ok(())
+    }
+}
@@ -2,20 +2,87 @@ use axum::response::{IntoResponse, Response};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    history::{HistoryPoint, Metric},
+    warnings::Warning,
+};
+
 #[derive(Serialize, Deserialize)]
 pub struct ForecastForDay {
     pub date_time: String,
     pub code: String,
     pub description: String,
     pub emoji: String,
-    pub min: i64,
-    pub max: i64,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
     pub uv: Option<f64>,
+    pub chance_of_rain: Option<i64>,
+    pub sunrise: Option<String>,
+    pub sunset: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForecastForHour {
+    pub date_time: String,
+    pub temperature: Option<f64>,
+    pub chance_of_rain: Option<i64>,
+    pub wind_speed: Option<f64>,
+    pub wind_direction: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct ForecastEndpointResponse {
     pub days: Vec<ForecastForDay>,
+    /// Only filled in for hourly forecasts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hours: Vec<ForecastForHour>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MarineForBlock {
+    pub date_time: String,
+    pub swell_height: Option<f64>,
+    pub swell_period: Option<f64>,
+    pub swell_direction: Option<String>,
+    pub wind_speed: Option<f64>,
+    pub wind_direction: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MarineEndpointResponse {
+    pub location: String,
+    pub blocks: Vec<MarineForBlock>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RainfallEndpointResponse {
+    pub date_time: String,
+    pub max_rate: f32,
+    pub coverage: f32,
+    pub rate: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RainSample {
+    pub date_time: String,
+    pub rate: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RainEndpointResponse {
+    pub radar: String,
+    pub bom_radar_id: String,
+    pub date_time: String,
+    pub rate: f32,
+    pub intensity: String,
+    pub trend: String,
+    pub expected_in_minutes: Option<u32>,
+    pub history: Vec<RainSample>,
+}
+
+#[derive(Serialize)]
+pub struct WarningsEndpointResponse {
+    pub warnings: Vec<Warning>,
 }
 
 pub enum AppError {
@@ -64,11 +131,107 @@ pub struct Location {
     pub type_id: i64,
 }
 
+/// Only the forecasts asked for with `forecasts=` are sent back.
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct Forecasts {
     pub weather: Weather,
     pub uv: Uv,
+    pub temperature: Graph<TemperatureEntry>,
+    pub wind: Graph<WindEntry>,
+    pub rainfallprobability: Graph<RainfallProbabilityEntry>,
+    pub rainfall: Graph<RainfallEntry>,
+    pub sunrisesunset: Graph<SunriseSunsetEntry>,
+    pub tides: Graph<TideEntry>,
+    pub moonphases: Graph<MoonPhaseEntry>,
+    pub swell: Graph<SwellEntry>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Graph<T> {
+    pub days: Vec<GraphDay<T>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphDay<T> {
+    pub date_time: String,
+    pub entries: Vec<T>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemperatureEntry {
+    pub date_time: String,
+    pub temperature: f64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindEntry {
+    pub date_time: String,
+    pub speed: f64,
+    pub direction: f64,
+    pub direction_text: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RainfallProbabilityEntry {
+    pub date_time: String,
+    pub probability: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwellEntry {
+    pub date_time: String,
+    /// Direction the swell comes from in degrees.
+    pub direction: f64,
+    pub direction_text: String,
+    /// Height in metres.
+    pub height: f64,
+    /// Period in seconds.
+    pub period: f64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SunriseSunsetEntry {
+    pub first_light_date_time: String,
+    pub rise_date_time: String,
+    pub set_date_time: String,
+    pub last_light_date_time: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TideEntry {
+    pub date_time: String,
+    /// Height in metres.
+    pub height: f64,
+    /// `high` or `low`.
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoonPhaseEntry {
+    pub date_time: String,
+    pub phase: String,
+    pub percentage_illuminated: f64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RainfallEntry {
+    pub date_time: String,
+    pub start_range: Option<i64>,
+    pub end_range: Option<i64>,
+    pub range_code: String,
+    pub probability: i64,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -135,3 +298,79 @@ pub struct Alert {
     pub start_date_time: String,
     pub end_date_time: String,
 }
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObservationsProduct {
+    pub observations: Observations,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Observations {
+    pub data: Vec<Observation>,
+}
+
+/// A single reading from a station, anything the station doesn't measure is null.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    pub wmo: i64,
+    pub name: String,
+    pub aifstime_utc: String,
+    pub air_temp: Option<f64>,
+    pub apparent_t: Option<f64>,
+    pub dewpt: Option<f64>,
+    pub rel_hum: Option<i64>,
+    pub press: Option<f64>,
+    pub wind_dir: Option<String>,
+    pub wind_spd_kmh: Option<i64>,
+    pub gust_kmh: Option<i64>,
+    /// Rain since 9am in mm, sent as a string and "-" when missing.
+    pub rain_trace: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ObservationsEndpointResponse {
+    pub location: String,
+    pub observation: Observation,
+}
+
+#[derive(Serialize)]
+pub struct HistoryEndpointResponse {
+    pub location: String,
+    pub metric: Metric,
+    pub unit: String,
+    pub points: Vec<HistoryPoint>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenMeteoForecast {
+    pub timezone: String,
+    pub utc_offset_seconds: i32,
+    #[serde(default)]
+    pub daily: OpenMeteoDaily,
+    #[serde(default)]
+    pub hourly: OpenMeteoHourly,
+}
+
+/// Each field has one value per day, in the same order as `time`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenMeteoDaily {
+    pub time: Vec<String>,
+    pub weather_code: Vec<Option<i64>>,
+    pub temperature_2m_max: Vec<Option<f64>>,
+    pub temperature_2m_min: Vec<Option<f64>>,
+    pub uv_index_max: Vec<Option<f64>>,
+    pub precipitation_probability_max: Vec<Option<i64>>,
+    #[serde(default)]
+    pub sunrise: Vec<Option<String>>,
+    #[serde(default)]
+    pub sunset: Vec<Option<String>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenMeteoHourly {
+    pub time: Vec<String>,
+    pub temperature_2m: Vec<Option<f64>>,
+    pub precipitation_probability: Vec<Option<i64>>,
+    pub wind_speed_10m: Vec<Option<f64>>,
+    pub wind_direction_10m: Vec<Option<f64>>,
+}
@@ -0,0 +1,414 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use twilight_http::Client as HttpClient;
+use twilight_model::{channel::message::Embed, id::Id, util::Timestamp};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder};
+
+use crate::source::{ImageSource, SourceError};
+
+const WARNINGS_PATH: &str = "/anon/gen/fwo";
+
+/// Only warnings issued this recently are posted to channels, so the first
+/// poll doesn't post everything that is already active.
+const RECENT_WARNING_HOURS: i64 = 2;
+
+#[derive(thiserror::Error, Debug)]
+pub enum WarningsError {
+    #[error("a image source error occurred: {0}")]
+    Source(#[from] SourceError),
+
+    #[error("a sql error occurred: {0}")]
+    Sql(#[from] sqlx::Error),
+
+    #[error("a xml error occurred: {0}")]
+    Xml(#[from] quick_xml::DeError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum State {
+    Nsw,
+    Nt,
+    Qld,
+    Sa,
+    Tas,
+    Vic,
+    Wa,
+}
+
+impl State {
+    pub const ALL: [State; 7] = [
+        Self::Nsw,
+        Self::Nt,
+        Self::Qld,
+        Self::Sa,
+        Self::Tas,
+        Self::Vic,
+        Self::Wa,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Nsw => "NSW",
+            Self::Nt => "NT",
+            Self::Qld => "QLD",
+            Self::Sa => "SA",
+            Self::Tas => "TAS",
+            Self::Vic => "VIC",
+            Self::Wa => "WA",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|s| s.code().eq_ignore_ascii_case(code))
+    }
+
+    /// The NSW feed also covers the ACT.
+    fn feed_path(&self) -> String {
+        let product = match self {
+            Self::Nsw => "IDZ00054",
+            Self::Nt => "IDZ00055",
+            Self::Qld => "IDZ00056",
+            Self::Sa => "IDZ00057",
+            Self::Tas => "IDZ00058",
+            Self::Vic => "IDZ00059",
+            Self::Wa => "IDZ00060",
+        };
+
+        format!(
+            "{WARNINGS_PATH}/{product}.warnings_{}.xml",
+            self.code().to_lowercase()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningKind {
+    SevereWeather,
+    FireWeather,
+    Flood,
+    Marine,
+    Other,
+}
+
+impl WarningKind {
+    const ALL: [WarningKind; 5] = [
+        Self::SevereWeather,
+        Self::FireWeather,
+        Self::Flood,
+        Self::Marine,
+        Self::Other,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SevereWeather => "severe-weather",
+            Self::FireWeather => "fire-weather",
+            Self::Flood => "flood",
+            Self::Marine => "marine",
+            Self::Other => "other",
+        }
+    }
+
+    fn from_name(name: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|k| k.name() == name)
+            .unwrap_or(Self::Other)
+    }
+
+    /// The feeds don't say what kind a warning is, the title always does.
+    fn from_title(title: &str) -> Self {
+        let title = title.to_lowercase();
+        if title.contains("fire") {
+            Self::FireWeather
+        } else if title.contains("flood") {
+            Self::Flood
+        } else if ["marine", "coastal", "gale", "wind warning", "surf"]
+            .iter()
+            .any(|k| title.contains(k))
+        {
+            Self::Marine
+        } else if ["severe", "thunderstorm", "cyclone", "heatwave"]
+            .iter()
+            .any(|k| title.contains(k))
+        {
+            Self::SevereWeather
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Self::SevereWeather => "⛈️",
+            Self::FireWeather => "🔥",
+            Self::Flood => "🌊",
+            Self::Marine => "⚓",
+            Self::Other => "⚠️",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Warning {
+    pub id: String,
+    pub state: State,
+    pub kind: WarningKind,
+    pub title: String,
+    pub link: String,
+    pub issued_at: DateTime<Utc>,
+}
+
+impl Warning {
+    pub fn embed(&self) -> Embed {
+        let mut embed = EmbedBuilder::new()
+            .title(format!("{} {}", self.kind.emoji(), self.title))
+            .url(&self.link)
+            .color(0xcc6666)
+            .footer(EmbedFooterBuilder::new(self.state.code()));
+
+        if let Ok(timestamp) = Timestamp::from_secs(self.issued_at.timestamp()) {
+            embed = embed.timestamp(timestamp);
+        }
+
+        embed.build()
+    }
+}
+
+#[derive(Deserialize)]
+struct Rss {
+    channel: RssChannel,
+}
+
+#[derive(Deserialize)]
+struct RssChannel {
+    #[serde(rename = "item", default)]
+    items: Vec<RssItem>,
+}
+
+#[derive(Deserialize)]
+struct RssItem {
+    title: String,
+    link: String,
+    #[serde(rename = "pubDate")]
+    pub_date: String,
+    guid: Option<RssGuid>,
+}
+
+#[derive(Deserialize)]
+struct RssGuid {
+    #[serde(rename = "$text")]
+    value: String,
+}
+
+/// Parses a state warnings rss feed (e.g. `IDZ00060.warnings_wa.xml`), items
+/// that can't be read are skipped.
+pub fn parse_feed(state: State, xml: &str) -> Result<Vec<Warning>, WarningsError> {
+    let rss: Rss = quick_xml::de::from_str(xml)?;
+
+    let warnings = rss
+        .channel
+        .items
+        .into_iter()
+        .filter_map(|item| {
+            let issued_at = match DateTime::parse_from_rfc2822(&item.pub_date) {
+                Ok(issued_at) => issued_at.to_utc(),
+                Err(e) => {
+                    tracing::warn!("skipping warning {}: {e}", item.link);
+                    return None;
+                }
+            };
+
+            Some(Warning {
+                id: item.guid.map_or_else(|| item.link.clone(), |g| g.value),
+                state,
+                kind: WarningKind::from_title(&item.title),
+                title: item.title,
+                link: item.link,
+                issued_at,
+            })
+        })
+        .collect();
+
+    Ok(warnings)
+}
+
+/// Polls every state feed, stores what's in them and marks anything that
+/// dropped out as no longer active. Returns warnings that weren't seen before.
+/// A state that fails is logged and left as it was.
+pub async fn refresh_warnings(source: &dyn ImageSource, db: &PgPool) -> Vec<Warning> {
+    let mut new_warnings = Vec::new();
+
+    for state in State::ALL {
+        match refresh_state(source, db, state).await {
+            Ok(warnings) => new_warnings.extend(warnings),
+            Err(e) => tracing::error!("error refreshing warnings for {}: {e}", state.code()),
+        }
+    }
+
+    new_warnings
+}
+
+async fn refresh_state(
+    source: &dyn ImageSource,
+    db: &PgPool,
+    state: State,
+) -> Result<Vec<Warning>, WarningsError> {
+    let feed = source.retrieve(&state.feed_path()).await?;
+    let warnings = parse_feed(state, &String::from_utf8_lossy(&feed))?;
+    let ids = warnings.iter().map(|w| w.id.clone()).collect::<Vec<_>>();
+
+    let mut new_warnings = Vec::new();
+    for warning in warnings {
+        let inserted = sqlx::query!(
+            r#"INSERT INTO warnings (id, state, kind, title, link, issued_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (id) DO NOTHING"#,
+            warning.id,
+            warning.state.code(),
+            warning.kind.name(),
+            warning.title,
+            warning.link,
+            warning.issued_at.naive_utc()
+        )
+        .execute(db)
+        .await?;
+
+        if inserted.rows_affected() > 0 {
+            new_warnings.push(warning);
+        }
+    }
+
+    sqlx::query!(
+        "UPDATE warnings SET active = (id = ANY($2)) WHERE state = ($1)",
+        state.code(),
+        &ids
+    )
+    .execute(db)
+    .await?;
+
+    Ok(new_warnings)
+}
+
+pub async fn active_warnings(
+    db: &PgPool,
+    state: Option<State>,
+) -> Result<Vec<Warning>, WarningsError> {
+    let warnings = sqlx::query!(
+        r#"SELECT * FROM warnings
+            WHERE active AND ($1::TEXT IS NULL OR state = $1)
+            ORDER BY issued_at DESC"#,
+        state.map(|s| s.code())
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .filter_map(|row| {
+        Some(Warning {
+            state: State::from_code(&row.state)?,
+            kind: WarningKind::from_name(&row.kind),
+            id: row.id,
+            title: row.title,
+            link: row.link,
+            issued_at: row.issued_at.and_utc(),
+        })
+    })
+    .collect();
+
+    Ok(warnings)
+}
+
+/// Posts recently issued warnings to every channel subscribed to their state.
+pub async fn post_new_warnings(
+    db: &PgPool,
+    http: &HttpClient,
+    warnings: &[Warning],
+) -> Result<(), WarningsError> {
+    let recent = Utc::now() - TimeDelta::hours(RECENT_WARNING_HOURS);
+
+    for warning in warnings.iter().filter(|w| w.issued_at >= recent) {
+        let channels = sqlx::query_scalar!(
+            "SELECT channel_id FROM warning_subscriptions WHERE state = ($1)",
+            warning.state.code()
+        )
+        .fetch_all(db)
+        .await?;
+
+        let embed = warning.embed();
+        for channel_id in channels {
+            tracing::info!("posting warning {} to {channel_id}", warning.id);
+            if let Err(e) = http
+                .create_message(Id::new(channel_id as u64))
+                .embeds(&[embed.clone()])
+                .await
+            {
+                tracing::error!("error posting warning {} to {channel_id}: {e}", warning.id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    const FEED: &str = include_str!("../tests/fixtures/ftp/anon/gen/fwo/IDZ00060.warnings_wa.xml");
+
+    #[test]
+    fn parses_feed() {
+        let warnings = parse_feed(State::Wa, FEED).unwrap();
+
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|w| (w.id.as_str(), w.kind, w.issued_at.to_rfc3339()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "IDW21033-202504140020",
+                    WarningKind::SevereWeather,
+                    "2025-04-14T00:20:31+00:00".to_owned()
+                ),
+                // no guid, the link stands in for it
+                (
+                    "http://www.bom.gov.au/wa/warnings/marinewind.shtml",
+                    WarningKind::Marine,
+                    "2025-04-13T23:55:02+00:00".to_owned()
+                ),
+            ]
+        );
+        assert!(warnings.iter().all(|w| w.state == State::Wa));
+    }
+
+    #[test]
+    fn invalid_feed() {
+        assert!(matches!(
+            parse_feed(State::Wa, "<rss><channel>"),
+            Err(WarningsError::Xml(_))
+        ));
+    }
+
+    #[rstest]
+    #[case(
+        "Fire Weather Warning for Central Wheat Belt",
+        WarningKind::FireWeather
+    )]
+    #[case("Flood Warning for the Swan River", WarningKind::Flood)]
+    #[case("Strong Wind Warning for Perth Local Waters", WarningKind::Marine)]
+    #[case("Hazardous Surf Warning", WarningKind::Marine)]
+    #[case("Severe Thunderstorm Warning", WarningKind::SevereWeather)]
+    #[case("Tropical Cyclone Advice", WarningKind::SevereWeather)]
+    #[case("Frost Warning", WarningKind::Other)]
+    fn kind_from_title(#[case] title: &str, #[case] expected: WarningKind) {
+        assert_eq!(WarningKind::from_title(title), expected);
+    }
+}
@@ -1,8 +1,16 @@
-use std::collections::HashSet;
-
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
 use tracing::instrument;
 
-use crate::types::WillyWeatherForecast;
+use crate::{
+    forecast::{
+        localise, parse_time_zone, DailyForecast, ForecastDay, ForecastError, ForecastHour,
+        ForecastLocation, ForecastProvider, HourlyForecast, Provider,
+    },
+    types::{Graph, Location, WillyWeatherForecast},
+};
 
 #[derive(Clone, Debug)]
 pub struct WillyWeatherAPI {
@@ -21,6 +29,12 @@ pub enum WillyWeatherAPIError {
 impl WillyWeatherAPI {
     const FORECAST_API_TEMPLATE: &str =
         "https://api.willyweather.com.au/v2/{API_KEY}/locations/{LOCATION_ID}/weather.json";
+    const LOCATION_API_TEMPLATE: &str =
+        "https://api.willyweather.com.au/v2/{API_KEY}/locations/{LOCATION_ID}.json";
+    const SEARCH_API_TEMPLATE: &str = "https://api.willyweather.com.au/v2/{API_KEY}/search.json";
+    const SEARCH_LIMIT: &str = "10";
+    const DAILY_FORECASTS: &str = "weather,uv,rainfall,sunrisesunset";
+    const HOURLY_FORECASTS: &str = "temperature,wind,rainfallprobability";
     pub const PERTH_ID: &str = "14576";
 
     pub fn new(api_key: String) -> Self {
@@ -30,11 +44,43 @@ impl WillyWeatherAPI {
         }
     }
 
-    pub fn get_locations() -> HashSet<(String, String)> {
-        HashSet::from_iter(vec![
-            ("Perth".to_owned(), Self::PERTH_ID.to_owned()),
-            ("Australind".to_owned(), "15864".to_owned()),
-        ])
+    /// Locations matching a partial name or postcode.
+    #[instrument(skip(self))]
+    pub async fn search_locations(
+        &self,
+        query: &str,
+    ) -> Result<Vec<Location>, WillyWeatherAPIError> {
+        let url = Self::SEARCH_API_TEMPLATE.replace("{API_KEY}", &self.api_key);
+
+        let response = self
+            .http
+            .get(url)
+            .query(&[("query", query), ("limit", Self::SEARCH_LIMIT)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<Location>>()
+            .await?;
+
+        Ok(response)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_location(&self, id: &str) -> Result<Location, WillyWeatherAPIError> {
+        let url = Self::LOCATION_API_TEMPLATE
+            .replace("{API_KEY}", &self.api_key)
+            .replace("{LOCATION_ID}", id);
+
+        let response = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Location>()
+            .await?;
+
+        Ok(response)
     }
 
     #[instrument(skip(self))]
@@ -42,6 +88,65 @@ impl WillyWeatherAPI {
         &self,
         id: &str,
         days: &i64,
+    ) -> Result<WillyWeatherForecast, WillyWeatherAPIError> {
+        self.get_forecasts(id, Self::DAILY_FORECASTS, days).await
+    }
+
+    /// Hourly temperature, wind and rain chance graphs.
+    #[instrument(skip(self))]
+    pub async fn get_forecast_graphs(
+        &self,
+        id: &str,
+        days: &i64,
+    ) -> Result<WillyWeatherForecast, WillyWeatherAPIError> {
+        self.get_forecasts(id, Self::HOURLY_FORECASTS, days).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_sunrise_sunset(
+        &self,
+        id: &str,
+        days: &i64,
+    ) -> Result<WillyWeatherForecast, WillyWeatherAPIError> {
+        self.get_forecasts(id, "sunrisesunset", days).await
+    }
+
+    /// Tides come from the closest tide station, inland locations have none.
+    #[instrument(skip(self))]
+    pub async fn get_tides(
+        &self,
+        id: &str,
+        days: &i64,
+    ) -> Result<WillyWeatherForecast, WillyWeatherAPIError> {
+        self.get_forecasts(id, "tides", days).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_moon_phases(
+        &self,
+        id: &str,
+        days: &i64,
+    ) -> Result<WillyWeatherForecast, WillyWeatherAPIError> {
+        self.get_forecasts(id, "moonphases", days).await
+    }
+
+    /// Swell and wind, swell is only sent for coastal locations. There's no
+    /// `marine` graph among the documented forecast types, wave conditions
+    /// come from `swell`.
+    #[instrument(skip(self))]
+    pub async fn get_marine(
+        &self,
+        id: &str,
+        days: &i64,
+    ) -> Result<WillyWeatherForecast, WillyWeatherAPIError> {
+        self.get_forecasts(id, "swell,wind", days).await
+    }
+
+    async fn get_forecasts(
+        &self,
+        id: &str,
+        forecasts: &str,
+        days: &i64,
     ) -> Result<WillyWeatherForecast, WillyWeatherAPIError> {
         let url = Self::FORECAST_API_TEMPLATE
             .replace("{API_KEY}", &self.api_key)
@@ -50,7 +155,7 @@ impl WillyWeatherAPI {
         let response = self
             .http
             .get(url)
-            .query(&[("forecasts", "weather,uv"), ("days", &days.to_string())])
+            .query(&[("forecasts", forecasts), ("days", &days.to_string())])
             .send()
             .await?
             .error_for_status()?
@@ -60,3 +165,134 @@ impl WillyWeatherAPI {
         Ok(response)
     }
 }
+
+/// Times are local to the location without an offset.
+pub fn parse_date_time(
+    date_time: &str,
+    time_zone: Tz,
+) -> Result<DateTime<FixedOffset>, ForecastError> {
+    let date_time = NaiveDateTime::parse_from_str(date_time, "%Y-%m-%d %H:%M:%S")?;
+    localise(date_time, time_zone)
+}
+
+/// Flattens a graph into its entries with parsed times, oldest first.
+pub fn graph_entries<T>(
+    graph: Graph<T>,
+    time_zone: Tz,
+    date_time: impl Fn(&T) -> &str,
+) -> Result<Vec<(DateTime<FixedOffset>, T)>, ForecastError> {
+    graph
+        .days
+        .into_iter()
+        .flat_map(|day| day.entries)
+        .map(|entry| Ok((parse_date_time(date_time(&entry), time_zone)?, entry)))
+        .collect()
+}
+
+#[async_trait]
+impl ForecastProvider for WillyWeatherAPI {
+    async fn get_forecast(
+        &self,
+        location: &ForecastLocation,
+        days: i64,
+    ) -> Result<DailyForecast, ForecastError> {
+        let id = location
+            .willyweather_id
+            .as_deref()
+            .ok_or_else(|| location.not_configured(Provider::WillyWeather))?;
+
+        let forecast = WillyWeatherAPI::get_forecast(self, id, &days).await?;
+        let time_zone = parse_time_zone(&forecast.location.time_zone)?;
+        let uv_days = forecast.forecasts.uv.days;
+        let rainfall_days = forecast.forecasts.rainfall.days;
+        let sun_days = forecast.forecasts.sunrisesunset.days;
+        let mut days = Vec::with_capacity(forecast.forecasts.weather.days.len());
+
+        for (i, day) in forecast.forecasts.weather.days.into_iter().enumerate() {
+            let entry = day
+                .entries
+                .into_iter()
+                .next()
+                .context("must have entries")
+                .map_err(WillyWeatherAPIError::Unknown)?;
+            let sun = sun_days.get(i).and_then(|sun| sun.entries.first());
+
+            days.push(ForecastDay {
+                date_time: parse_date_time(&entry.date_time, time_zone)?,
+                precis_code: entry.precis_code,
+                description: entry.precis,
+                min: Some(entry.min),
+                max: Some(entry.max),
+                uv: uv_days.get(i).map(|uv| uv.alert.max_index),
+                chance_of_rain: rainfall_days
+                    .get(i)
+                    .and_then(|rainfall| rainfall.entries.first())
+                    .map(|rainfall| rainfall.probability),
+                sunrise: sun
+                    .map(|sun| parse_date_time(&sun.rise_date_time, time_zone))
+                    .transpose()?,
+                sunset: sun
+                    .map(|sun| parse_date_time(&sun.set_date_time, time_zone))
+                    .transpose()?,
+            });
+        }
+
+        Ok(DailyForecast {
+            location: location.name.clone(),
+            provider: Provider::WillyWeather,
+            days,
+        })
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        location: &ForecastLocation,
+        hours: i64,
+    ) -> Result<HourlyForecast, ForecastError> {
+        let id = location
+            .willyweather_id
+            .as_deref()
+            .ok_or_else(|| location.not_configured(Provider::WillyWeather))?;
+
+        // the graphs start at midnight so today is always needed as well
+        let days = hours.div_ceil(24) + 1;
+        let forecast = self.get_forecast_graphs(id, &days).await?;
+        let time_zone = parse_time_zone(&forecast.location.time_zone)?;
+        let forecasts = forecast.forecasts;
+
+        let temperatures = graph_entries(forecasts.temperature, time_zone, |e| &e.date_time)?;
+        let winds = graph_entries(forecasts.wind, time_zone, |e| &e.date_time)?;
+        // rain chance is only every 3 hours
+        let rain_chances =
+            graph_entries(forecasts.rainfallprobability, time_zone, |e| &e.date_time)?;
+
+        let current_hour = Utc::now() - TimeDelta::hours(1);
+        let hours = temperatures
+            .into_iter()
+            .filter(|(time, _)| *time > current_hour)
+            .take(hours.max(0) as usize)
+            .map(|(date_time, temperature)| {
+                let wind = winds.iter().find(|(time, _)| *time == date_time);
+                let chance_of_rain = rain_chances
+                    .iter()
+                    .rev()
+                    .find(|(time, _)| *time <= date_time)
+                    .map(|(_, rain)| rain.probability);
+
+                ForecastHour {
+                    date_time,
+                    temperature: Some(temperature.temperature),
+                    chance_of_rain,
+                    wind_speed: wind.map(|(_, wind)| wind.speed),
+                    wind_direction: wind.map(|(_, wind)| wind.direction_text.clone()),
+                }
+            })
+            .collect();
+
+        Ok(HourlyForecast {
+            location: location.name.clone(),
+            provider: Provider::WillyWeather,
+            hours,
+        })
+    }
+}